@@ -0,0 +1,44 @@
+//! Render a template with the fields of a struct using the
+//! `Variables` trait, instead of making a `HashMap` for each of them.
+use std::borrow::Cow;
+use std::error::Error;
+
+use string_template_plus::{RenderOptions, Template, Variables};
+
+struct Person {
+    name: String,
+    age: u32,
+    nickname: Option<String>,
+}
+
+impl Variables for Person {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        match name {
+            "name" => Some(Cow::Borrowed(&self.name)),
+            "age" => Some(Cow::Owned(self.age.to_string())),
+            "nickname" => self.nickname.as_deref().map(Cow::Borrowed),
+            _ => None,
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let templ = Template::parse_template("{nickname?name} is {age} years old")?;
+    let options = RenderOptions::default();
+    let people = [
+        Person {
+            name: "John".into(),
+            age: 30,
+            nickname: Some("Johnny".into()),
+        },
+        Person {
+            name: "Jane".into(),
+            age: 28,
+            nickname: None,
+        },
+    ];
+    for person in &people {
+        println!("{}", templ.render_with(person, &options)?);
+    }
+    Ok(())
+}
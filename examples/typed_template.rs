@@ -0,0 +1,47 @@
+//! What a `template!("Hello {name}, you are {age:calc(+1)} next year{suffix?}")`
+//! macro could generate, written by hand.
+use std::collections::HashMap;
+use std::error::Error;
+
+use string_template_plus::{RenderOptions, Template};
+
+const TEMPLATE: &str = "Hello {name}, you are {age:calc(+1)} next year{suffix?}";
+
+/// One field per variable, the optional ones are [`Option`]s
+struct Greeting<'a> {
+    name: &'a str,
+    age: &'a str,
+    suffix: Option<&'a str>,
+}
+
+impl Greeting<'_> {
+    fn render(&self) -> Result<String, Box<dyn Error>> {
+        let mut variables = HashMap::new();
+        variables.insert("name".to_string(), self.name.to_string());
+        variables.insert("age".to_string(), self.age.to_string());
+        if let Some(suffix) = self.suffix {
+            variables.insert("suffix".to_string(), suffix.to_string());
+        }
+        let op = RenderOptions {
+            variables,
+            ..Default::default()
+        };
+        // the macro would have parsed it at compile time already
+        Ok(op.render(&Template::parse_template(TEMPLATE)?)?)
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // this is the check the macro would do at compile time
+    let templ = Template::parse_template(TEMPLATE)?;
+    assert_eq!(templ.required_variables(), ["name", "age"]);
+    assert_eq!(templ.variables(), ["name", "age", "suffix"]);
+
+    let greeting = Greeting {
+        name: "John",
+        age: "30",
+        suffix: Some("!"),
+    };
+    println!("{}", greeting.render()?);
+    Ok(())
+}
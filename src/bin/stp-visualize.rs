@@ -1,19 +1,38 @@
 use std::env;
 use std::error::Error;
+use std::io::{stdout, IsTerminal};
+use string_template_plus::transformers::transformer_specs;
 use string_template_plus::{Render, Template};
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
     if args.len() == 1 {
-        eprintln!("Provide template files to parse");
+        eprintln!("Provide template files to parse, or --transformers to list transformers");
+        return Ok(());
+    }
+    if args[1] == "--transformers" {
+        for spec in transformer_specs() {
+            let max = spec.max_args.map(|m| m.to_string()).unwrap_or_default();
+            println!(
+                "{}({}..{}): {}",
+                spec.name, spec.min_args, max, spec.description
+            );
+        }
         return Ok(());
     }
     for filepath in args[1..].iter() {
         println!("*** {} ***", filepath);
         let contents = std::fs::read_to_string(filepath)?;
         let templ = Template::parse_template(&contents)?;
-        templ.print();
+        if stdout().is_terminal() {
+            templ.print();
+        } else {
+            templ.print_plain();
+        }
         println!();
+        if let Err(e) = templ.check_transformers() {
+            eprintln!("Error: {}", e);
+        }
         // println!("--------");
         // let vars: HashSet<&str> = templ.parts().iter().flat_map(|p| p.variables()).collect();
         // println!("Variables: {:?}", vars);
@@ -1,4 +1,4 @@
-use std::{error::Error, fmt};
+use std::{error::Error, fmt, time::Duration};
 
 /// Errors for the render template
 #[derive(Debug)]
@@ -11,6 +11,14 @@ pub enum RenderTemplateError {
     AllVariablesNotFound(Vec<String>),
     /// Error from Transformers
     TransformerError(TransformerError),
+    /// Command exited with a failure, with the exit status and the output before failing
+    CommandFailed(String, String, String),
+    /// Parts are nested deeper than the maximum depth allowed
+    MaxDepthExceeded(usize),
+    /// Lisp expression took longer than the timeout to evaluate
+    LispTimeout(String, Duration),
+    /// Command runs a program that is not allowed, with the command and the program
+    CommandNotAllowed(String, String),
 }
 
 /// Errors for the transformers
@@ -28,6 +36,12 @@ pub enum TransformerError {
     InvalidValueType(&'static str, &'static str),
     /// The argument provided is not the correct type
     InvalidArgumentType(&'static str, String, &'static str),
+    /// The expression provided as the argument can't be evaluated
+    InvalidExpression(&'static str, String, String),
+    /// The value is too big or small for the transformer
+    ValueOutOfRange(&'static str, String, &'static str),
+    /// The transformer is disabled in the render options
+    DisabledTransformer(String),
 }
 
 impl Error for RenderTemplateError {}
@@ -58,6 +72,13 @@ impl fmt::Display for TransformerError {
             Self::InvalidArgumentType(fun, g, t) => {
                 write!(f, "{fun} argument {g} needs to be of {t} type")
             }
+            Self::InvalidExpression(fun, expr, msg) => {
+                write!(f, "{fun} expression {expr} is invalid: {msg}")
+            }
+            Self::ValueOutOfRange(fun, val, range) => {
+                write!(f, "{fun} value {val} is out of range, needs to be {range}")
+            }
+            Self::DisabledTransformer(fun) => write!(f, "{fun} transformer is disabled"),
         }
     }
 }
@@ -75,6 +96,18 @@ impl fmt::Display for RenderTemplateError {
                 write!(f, "None of the variables {vars:?} found")
             }
             Self::TransformerError(e) => e.fmt(f),
+            Self::CommandFailed(cmd, status, out) => {
+                write!(f, "Command {cmd} failed with {status}, output: {out:?}")
+            }
+            Self::MaxDepthExceeded(depth) => {
+                write!(f, "Template parts nested deeper than the max depth {depth}")
+            }
+            Self::LispTimeout(expr, timeout) => {
+                write!(f, "Lisp expression {expr} took longer than {timeout:?}")
+            }
+            Self::CommandNotAllowed(cmd, prog) => {
+                write!(f, "Command {cmd} runs {prog} which is not allowed")
+            }
         }
     }
 }
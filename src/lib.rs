@@ -9,14 +9,17 @@ shell commands running through [`Exec`].
 # Features
 - Parse the template from a `str` that's easy to write,
 - Support for alternatives in case some variables are not present,
-  Use `?` to separate the alternatives, uses whichever it can find first. If `?` is at the end, leaves it blank instead of erroring out.
+  Use `?` to separate the alternatives, uses whichever it can find first (and its transformers succeed). If `?` is at the end, leaves it blank instead of erroring out.
 - Support for literal strings inside the alternative options,
   You can use a literal string `"string"` enclosed in `"` as an alternative if you want to put something instead of blank at the end.
 - Support for the date time format using `chrono`,
-  You can use any format starting with `%` inside the variable placeholder `{}` to use a date time format supported by chrono.
+  You can use any format starting with `%` inside the variable placeholder `{}` to use a date time format supported by chrono. Formats that chrono doesn't support are errors while parsing the template.
 - Support for any arbitrary commands, etc.
-You can keep any command inside `$(` and `)` to run it and use the result in the template. You can use other format elements inside it.
+  You can keep any command inside `$(` and `)` to run it and use the result in the template. You can use other format elements inside it. Ending the command with `|stdin:{var}` passes the value of the last part to the command's standard input, like `$(jq .name|stdin:{json})`. Lisp expressions work inside the commands too, like `$(echo =(+ 1 2))`, they are evaluated before the command runs, so shell quotes don't stop them. Quotes inside the template are parsed as literal text, so escape them (`\"`) to pass them to the shell. The output keeps the trailing newline unless [`RenderOptions::trim_command_output`] is set.
 - Support for iterating (incremented with -N) strings with the same template conditions,
+- Support for rendering the same template with multiple sets of variables, in parallel with the `rayon` feature,
+- Support for comparing the rendered text with the expected one for tests, with the `diff` feature,
+- Support for reading the variables from JSON or TOML files, with the `json` and `toml` features,
 - Limited formatting support like UPCASE, downcase, float significant digits, etc. Look into [`transformers`] for more info.
 
 
@@ -86,6 +89,17 @@ variables: vars,
 # }
 ```
 
+Variables with empty values are used as they are, use `??` instead
+of `?` like `{nickname??name}` to skip them when they're empty.
+
+Characters with special meaning can be escaped with `\` to be used
+literally. Outside of `{}` any character can be escaped (like `\{` or
+`\$(`), while inside of `{}`, in the variables, the alternatives and
+the transformer arguments, only the characters `{}()?:,"\` are
+escaped. For example, `{x:repl(\,,;)}` replaces the commas with `;`
+and `{nickname?"who\?"}` gives `who?`. A `\` before other characters
+is kept, so regex patterns like `\d+` can be written as they are.
+
 Calculations can be written in lisp like language, it supports simple
 functions. Using lisp can also allow you to write more complex
 logic. The lisp implementation is the one from
@@ -100,6 +114,9 @@ To access the values in lisp you can use the following functions:
 You need to quote the symbol to pass to the functions (e.g. (st+num
 'total) or (st+num "total").
 
+If the result of the expression is a string, it's used without the
+quotes, so transformers get the bare value.
+
 Else, you can just write the variables in braces like normal as well.
 
 there are two use cases.
@@ -108,6 +125,9 @@ there are two use cases.
 - Inside the `{}` that can be used as alternative to a variable, or
   with a transformer.
 
+If you want to write `=(` literally, escape it with [`ESCAPE_CHAR`]
+like `\=(`, and it'll not be evaluated as lisp.
+
 ```rust
 # use std::error::Error;
 # use std::collections::HashMap;
@@ -147,6 +167,7 @@ let rendered = templ
 wd: PathBuf::from("."),
 variables: vars,
 shell_commands: true,
+..Default::default()
             })
             .unwrap();
         assert_eq!(rendered, "L=12.34");
@@ -170,6 +191,7 @@ let rendered = templ
 wd: PathBuf::from("."),
 variables: vars,
 shell_commands: false,
+..Default::default()
             })
             .unwrap();
         assert_eq!(rendered, "L=$(printf %.2f 12.342323)");
@@ -196,6 +218,7 @@ let rendered = templ
 wd: PathBuf::from("."),
 variables: vars,
 shell_commands: false,
+..Default::default()
             })
             .unwrap();
         assert_eq!(rendered, output);
@@ -213,19 +236,77 @@ There are a few transformers available:
 | Transformer | Funtion                        | Arguments | Function                  | Example                  |
 |-------------|--------------------------------|-----------|---------------------------|--------------------------|
 | f           | [`transformers::float_format`] | [.]N      | only N number of decimal  | {"1.12":f(.1)} ⇒ 1.1     |
+| f           | [`transformers::float_format`] | [.]N,group | decimal and group digits | {"1234":f(.1,group)} ⇒ 1,234.0 |
 | case        | [`transformers::string_case`]  | up        | UPCASE a string           | {"na":case(up)} ⇒ NA     |
 | case        | [`transformers::string_case`]  | down      | downcase a string         | {"nA":case(down)} ⇒ na   |
 | case        | [`transformers::string_case`]  | proper    | Upcase the first letter   | {"nA":case(proper)} ⇒ Na |
 | case        | [`transformers::string_case`]  | title     | Title Case the string     | {"na":case(title)} ⇒ Na  |
+| case        | [`transformers::string_case`]  | up,tr     | UPCASE with Turkish rules | {"i":case(up,tr)} ⇒ İ    |
 | calc        | [`transformers::calc`]         | [+-*\/^]N | Airthmatic calculation    | {"1":calc(+1*2^2)} ⇒ 16  |
 | calc        | [`transformers::calc`]         | [+-*\/^]N | Airthmatic calculation    | {"1":calc(+1,-1)} ⇒ 2,0  |
+| math        | [`transformers::math`]         | expr      | calculation w/ precedence | {"1":math(+1*2)} ⇒ 3     |
+| math        | [`transformers::math`]         | expr      | calculation w/ precedence | {"1":math((+1)*2)} ⇒ 4   |
+| math        | [`transformers::math`]         | expr      | calculation w/ precedence | {"16":math(sqrt+1)} ⇒ 5  |
 | count       | [`transformers::count`]        | str       | count str occurance       | {"nata":count(a)} ⇒ 2    |
 | repl        | [`transformers::replace`]      | str1,str2 | replace str1 by str2      | {"nata":rep(a,o)} ⇒ noto |
+| repl        | [`transformers::replace`]      | str1,str2,N | replace first N str1    | {"nata":repl(a,o,1)} ⇒ nota |
 | q           | [`transformers::quote`]        | [str1]    | quote with str1, or ""    | {"nata":q()} ⇒ "noto"    |
 | take        | [`transformers::take`]         | str,N     | take Nth group sep by str | {"nata":take(a,2)} ⇒ "t" |
 | trim        | [`transformers::trim`]         | str       | trim the string with str  | {"nata":trim(a)} ⇒ "nat" |
+| wc          | [`transformers::word_count`]   | words     | count words               | {"na ta":wc(words)} ⇒ 2  |
+| wc          | [`transformers::word_count`]   | lines     | count lines               | {"na":wc(lines)} ⇒ 1     |
+| wc          | [`transformers::word_count`]   | chars     | count characters          | {"nata":wc(chars)} ⇒ 4   |
+| ltrim       | [`transformers::trim_left`]    | str       | trim only the left side   | {"nata":ltrim(n)} ⇒ "ata"|
+| rtrim       | [`transformers::trim_right`]   | str       | trim only the right side  | {"nata":rtrim(a)} ⇒ "nat"|
+| path        | [`transformers::path_sep`]     | unix      | use / as path separator   | {"a\\b":path(unix)} ⇒ a/b |
+| path        | [`transformers::path_sep`]     | win       | use \ as path separator   | {"a/b":path(win)} ⇒ a\b  |
+| path        | [`transformers::path_sep`]     | norm      | remove . and .. from path | {"a/../b":path(norm)} ⇒ b |
+| printf      | [`transformers::printf`]       | %fmt      | C style printf format     | {"1.2":printf(%05.2f)} ⇒ 01.20 |
+| unquote     | [`transformers::unquote`]      | [str1]    | remove quotes             | {"'na'":unquote()} ⇒ na  |
+| oxford      | [`transformers::oxford`]       | [str,conj]| list as English prose     | {"a,b,c":oxford(,)} ⇒ a, b, and c |
+| numalign    | [`transformers::num_align`]    | W[,D[,0]] | right align number        | {"-1.5":numalign(6,2)} ⇒ " -1.50" |
+| field       | [`transformers::field`]        | key[,s,s] | value of key=value pair   | {"a=1;b=2":field(b)} ⇒ 2 |
+| lisp        | [`transformers::lisp_transform`] | expr    | evaluate lisp with `self` | {"21":lisp((* 2 (st+num 'self)))} ⇒ 42 |
+| words       | [`transformers::words`]        | [keep]    | number in English words   | {"123":words()} ⇒ one hundred twenty-three |
+| unescape    | [`transformers::unescape`]     |           | interpret `\n`, `\t`, etc. | {"a\\nb":unescape()} ⇒ a⏎b |
+| isodur      | [`transformers::iso_duration`] | [fmt]     | ISO 8601 durations        | {"PT1H30M":isodur()} ⇒ 5400 |
+| plural      | [`transformers::plural`]       | s,p[,withnum] | singular or plural form | {"3":plural(item,items)} ⇒ items |
+| dateoffset  | [`transformers::date_offset`]  | fmt,off[,fmt] | shift the date        | {"2024-01-01":dateoffset(%F,-1d)} ⇒ 2023-12-31 |
+| when        | [`transformers::when`]         | pat,a[,b] | a if matches pat, else b  | {"503":when(re:^5,error,ok)} ⇒ error |
+| stripprefix | [`transformers::strip_prefix`] | str       | remove prefix str         | {"a.txt":stripprefix(a.)} ⇒ txt |
+| stripsuffix | [`transformers::strip_suffix`] | str       | remove suffix str         | {"a.txt":stripsuffix(.txt)} ⇒ a |
+| ext         | [`transformers::extension`]    |           | file extension            | {"a/b.txt":ext()} ⇒ txt  |
+| basename    | [`transformers::basename`]     |           | last path component       | {"a/b.txt":basename()} ⇒ b.txt |
+| dirname     | [`transformers::dirname`]      |           | parent directory          | {"a/b.txt":dirname()} ⇒ a |
+| showif      | [`transformers::show_if`]      | var       | value if var is truthy    | {"a":showif(verbose)} ⇒ a |
+| levenshtein | [`transformers::levenshtein`]  | str       | edit distance from str    | {"kitten":levenshtein(sitting)} ⇒ 3 |
+| center      | [`transformers::center`]       | W[,C]     | center text in width W    | {"ab":center(6,-)} ⇒ "--ab--" |
+| enumerate   | [`transformers::enumerate`]    | [str,fmt,n]| list as numbered lines   | {"a,b":enumerate(,)} ⇒ "1. a\n2. b" |
+| oneline     | [`transformers::oneline`]      | [sep,tab] | join lines with sep       | {"a\nb":oneline(;)} ⇒ "a;b" |
+| bucket      | [`transformers::bucket`]       | N         | stable hash index 0..N    | {"alice":bucket(8)} ⇒ 7 |
+| reformat    | [`transformers::reformat`]     | re,fmt    | regex groups into fmt     | {"3-2024":reformat((\d+)-(\d+),$2/$1)} ⇒ 2024/3 |
+| luhn        | [`transformers::luhn`]         | [check]   | add or check Luhn digit   | {"7992739871":luhn()} ⇒ 79927398713 |
+| group       | [`transformers::group`]        | sep[,N..] or in | group digits        | {"1234567":group(in)} ⇒ 12,34,567 |
+| deaccent    | [`transformers::deaccent`]     |           | remove accents            | {"café":deaccent()} ⇒ cafe |
+| sig         | [`transformers::sig`]          | N         | N significant figures     | {"12345":sig(3)} ⇒ 12300 |
+| col         | [`transformers::col`]          | N[,delim] | Nth column like awk       | {"a   b c":col(2)} ⇒ b |
+| render      | [`transformers::render_template`] |        | render value as template  | {"{name}":render()} ⇒ world |
+| hexdump     | [`transformers::hexdump`]      | [N]       | hex dump, N bytes a line  | {"hi":hexdump(2)} ⇒ "00000000  68 69  \|hi\|" |
+| pick        | [`transformers::pick`]         | a,b,..    | argument at index value   | {"4":pick(a,b,c)} ⇒ b |
+| cmp         | [`transformers::cmp`]          | op,N,t,f  | t if value op N else f    | {"12":cmp(>,10,high,low)} ⇒ high |
+| accounting  | [`transformers::accounting`]   | [N]       | grouped, (negative)       | {"-1234.5":accounting(2)} ⇒ (1,234.50) |
+| dbg         | [`transformers::dbg`]          |           | debug info of value       | {"12.3":dbg()} ⇒ "12.3" (len=4, numeric) |
+| keep        | [`transformers::keep`]         | class     | keep chars of the class   | {"a-1_b":keep(alnum)} ⇒ a1b |
+| strip       | [`transformers::strip`]        | class     | remove chars of the class | {"v1.2":strip(digit)} ⇒ v. |
+| bar         | [`transformers::bar`]          | W[,f[,e]] | progress bar of width W   | {"50":bar(4)} ⇒ "[##  ]" |
+| once        | [`transformers::once`]         | [key]     | value only the first time | {"a":once()}{"a":once()} ⇒ a |
+| ago         | [`transformers::ago`]          | fmt       | time since date in words  | {"2024-03-07":ago(%F)} ⇒ 3 days ago |
+
+Note that `calc` calculates from left to right, so `{"1":calc(+1*2)}` gives 4, use `math` for the usual operator precedence, where `{"1":math(+1*2)}` gives 3.
 
-You can chain transformers ones after another for combined actions. For example, `count( ):calc(+1)` will give you total number of words in a sentence.
+You can chain transformers ones after another for combined actions. For example, `count( ):calc(+1)` will give you total number of spaces plus one, although `wc(words)` is better for counting words in a sentence.
+
+Chains used often can be given a name in [`RenderOptions::transformer_aliases`] and used like `{price:@money}`.
 
 Examples are in individual functions in [`transformers`].
 
@@ -266,26 +347,47 @@ for (t, r) in cases {
 # }
 ```
 
+# Typed Templates
+
+There is no macro in this crate yet, but the parts a compile time
+macro like `template!("hello {name}")` would need are public. It can
+parse the template with `Template::parse_template` to report the
+syntax errors, generate a struct with a field for each name in
+`Template::required_variables` (and `Option` fields for the rest of
+`Template::variables`), and render it by filling
+`RenderOptions::variables` from those fields. See
+`examples/typed_template.rs` for what such a generated struct would
+look like.
+
 # Limitations
 - You cannot use positional arguments in this template system, only named ones. `{}` will be replaced with empty string. Although you can use `"0"`, `"1"`, etc as variable names in the template and the render options variables.
 - I haven't tested variety of names, although they should work try to keep the names identifier friendly.
-- Currently doesn't have format specifiers, for now you can use the command options with `printf` bash command to format things the way you want, or use the transformers which have limited formatting capabilities.
-Like a template `this is $(printf "%05.2f" {weight}) kg.` should be rendered with the correct float formatting.
+- Currently doesn't have format specifiers, for now you can use the `printf` transformer, the command options with `printf` bash command to format things the way you want, or use the other transformers which have limited formatting capabilities.
+  Like a template `this is {weight:printf(%05.2f)} kg.` or `this is $(printf "%05.2f" {weight}) kg.` should be rendered with the correct float formatting.
 */
 use anyhow::Error;
-use chrono::Local;
-use colored::Colorize;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use colored::{ColoredString, Colorize};
 use lazy_static::lazy_static;
-use std::collections::HashMap;
-use std::io::Read;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use rust_lisp::model::Value;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::ops::Range;
 use std::path::PathBuf;
-use subprocess::Exec;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use subprocess::{Exec, Redirection};
 
 pub mod errors;
 pub mod lisp;
 pub mod transformers;
 
-/// Character to separate the variables. If the first variable is not present it'll use the one behind it and so on. Keep it at the end, if you want a empty string instead of error on missing variable.
+/// Character to separate the variables. If the first variable is not present, or its transformers fail, it'll use the one behind it and so on. Keep it at the end, if you want a empty string instead of error on missing variable. Variables with empty values are also skipped if [`RenderOptions::empty_is_missing`] is set, or only for the given variable when it's followed by two of them like `{name??other}`.
 pub static OPTIONAL_RENDER_CHAR: char = '?';
 /// Character that should be in the beginning of the variable to determine it as datetime format.
 pub static TIME_FORMAT_CHAR: char = '%';
@@ -293,10 +395,25 @@ pub static TIME_FORMAT_CHAR: char = '%';
 pub static LISP_START_CHAR: char = '=';
 /// Character that separates variable with format
 pub static VAR_TRANSFORM_SEP_CHAR: char = ':';
+/// Character that starts a section for the named output stream like
+/// `{>file.txt}`, see [`Template::render_streams`]
+pub static STREAM_START_CHAR: char = '>';
+/// Character that ends the current output stream section like `{<}`
+pub static STREAM_END_CHAR: char = '<';
 /// Quote characters to use to make a value literal instead of a variable. In combination with [`OPTIONAL_RENDER_CHAR`] it can be used as a default value when variable(s) is/are not present.
 pub static LITERAL_VALUE_QUOTE_CHAR: char = '"';
-/// Character to escape special meaning characters
+/// Character to escape special meaning characters, the character after it is used literally (e.g. `\{`, `\$(` or `\=(`).
+/// Inside the `{}` only the [`ESCAPED_CHARS`] are escaped, so the regex patterns like `\d` can be used as they are.
 pub static ESCAPE_CHAR: char = '\\';
+/// Characters that can be escaped with [`ESCAPE_CHAR`] inside the `{}`, in the variables, the
+/// alternatives (`?`) and the transformer arguments
+pub static ESCAPED_CHARS: &str = "{}()?:,\"\\";
+/// Default maximum nesting depth of the parts while rendering, see [`RenderOptions::max_depth`]
+pub static MAX_RENDER_DEPTH: usize = 64;
+
+/// Number of bytes read at a time by [`Template::render_stream`]
+static STREAM_CHUNK_SIZE: usize = 8 * 1024;
+
 /// Characters that should be replaced as themselves if presented as a variable
 static LITERAL_REPLACEMENTS: [&str; 3] = [
     "",  // to replace {} as empty string.
@@ -304,16 +421,122 @@ static LITERAL_REPLACEMENTS: [&str; 3] = [
     "}", // to replace {}} as }
 ];
 
-/// Runs a command and returns the output of the command or the error
-fn cmd_output(cmd: &str, wd: &PathBuf) -> Result<String, Error> {
-    let mut out: String = String::new();
-    Exec::shell(cmd)
-        .cwd(wd)
-        .stream_stdout()?
-        .read_to_string(&mut out)?;
-    Ok(out)
+/// Position of the first `sep` in the text that's not escaped with [`ESCAPE_CHAR`]
+fn find_unescaped(text: &str, sep: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == ESCAPE_CHAR {
+            escaped = true;
+        } else if c == sep {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Split the text at the `sep` that are not escaped with [`ESCAPE_CHAR`]
+pub(crate) fn split_unescaped(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(i) = find_unescaped(rest, sep) {
+        parts.push(&rest[..i]);
+        rest = &rest[(i + sep.len_utf8())..];
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Remove the [`ESCAPE_CHAR`] before the [`ESCAPED_CHARS`], the other
+/// characters after it are kept with the escape
+pub(crate) fn remove_escapes(text: &str) -> Cow<'_, str> {
+    if !text.contains(ESCAPE_CHAR) {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&n) if c == ESCAPE_CHAR && ESCAPED_CHARS.contains(n) => {
+                result.push(n);
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+    Cow::Owned(result)
+}
+
+/// Runs a command like [`cmd_output`], retrying on non-zero exit
+/// status as many times as [`RenderOptions::command_retries`]. The
+/// output of the last try is used if it still fails, unless
+/// [`RenderOptions::fail_on_command_error`] is set
+fn cmd_output_retry(cmd: &str, op: &RenderOptions, stdin: Option<&str>) -> Result<String, Error> {
+    let mut tries = 0;
+    loop {
+        match cmd_output(cmd, op, stdin) {
+            Err(e) => match e.downcast::<errors::RenderTemplateError>() {
+                Ok(errors::RenderTemplateError::CommandFailed(..))
+                    if tries < op.command_retries =>
+                {
+                    tries += 1;
+                    std::thread::sleep(op.command_retry_delay);
+                }
+                Ok(errors::RenderTemplateError::CommandFailed(_, _, out))
+                    if !op.fail_on_command_error =>
+                {
+                    return Ok(trim_output(out, op));
+                }
+                Ok(e) => return Err(e.into()),
+                Err(e) => return Err(e),
+            },
+            res => return res,
+        }
+    }
+}
+
+/// Runs a command and returns the output of the command or the
+/// error, the output before the failure is kept in the error if the
+/// command exits with non-zero status
+fn cmd_output(cmd: &str, op: &RenderOptions, stdin: Option<&str>) -> Result<String, Error> {
+    let mut exec = Exec::shell(cmd).cwd(&op.wd).stdout(Redirection::Pipe);
+    if let Some(input) = stdin {
+        exec = exec.stdin(input);
+    }
+    let capture = exec.capture()?;
+    let out = if op.command_output_lossy {
+        String::from_utf8_lossy(&capture.stdout).into_owned()
+    } else {
+        String::from_utf8(capture.stdout)?
+    };
+    if capture.exit_status.success() {
+        Ok(trim_output(out, op))
+    } else {
+        Err(errors::RenderTemplateError::CommandFailed(
+            cmd.to_string(),
+            format!("{:?}", capture.exit_status),
+            out,
+        )
+        .into())
+    }
+}
+
+/// Removes one trailing newline from the command output if
+/// [`RenderOptions::trim_command_output`] is set
+fn trim_output(out: String, op: &RenderOptions) -> String {
+    if op.trim_command_output {
+        let trimmed = out.strip_suffix('\n').unwrap_or(&out);
+        trimmed.strip_suffix('\r').unwrap_or(trimmed).to_string()
+    } else {
+        out
+    }
 }
 
+/// Text at the end of a command that marks the next part as its
+/// standard input, like in `$(cat|stdin:{var})`
+pub static CMD_STDIN_MARKER: &str = "|stdin:";
+
 /// Parts that make up a [`Template`]. You can have literal strings, variables, time date format, command, or optional format with [`OPTIONAL_RENDER_CHAR`].
 ///
 /// [`TemplatePart::Lit`] = Literal Strings like `"hi "` in `"hi {name}"`
@@ -321,9 +544,10 @@ fn cmd_output(cmd: &str, wd: &PathBuf) -> Result<String, Error> {
 /// [`TemplatePart::Time`] = Date time format like `"%F"` in `"Today: {%F}"`
 /// [`TemplatePart::Cmd`] = Command like `"echo world"` in `"hello $(echo world)"`
 /// [`TemplatePart::Any`] = Optional format like `"name?age"` in `"hello {name?age}"`
+/// [`TemplatePart::SkipEmpty`] = Empty alternative of `??` like in `"hello {name??age}"`
 ///
-/// [`TemplatePart::Cmd`] and [`TemplatePart::Any`] can in turn contain other [`TemplatePart`] inside them. Haven't tested on nesting complex ones within each other though.
-#[derive(Debug, Clone)]
+/// [`TemplatePart::Cmd`] and [`TemplatePart::Any`] can in turn contain other [`TemplatePart`] inside them. The nesting depth while rendering is limited by [`RenderOptions::max_depth`].
+#[derive(Debug, Clone, PartialEq)]
 pub enum TemplatePart {
     /// Literal string, keep them as they are
     Lit(String),
@@ -331,12 +555,14 @@ pub enum TemplatePart {
     Var(String, String),
     /// DateTime format, use [`chrono::Local`] in the given format
     Time(String),
-    /// Lisp expression to calculate with the transformer, last part is start..end of variables used in lisp
+    /// Lisp expression to calculate with the transformer, last part is start..end of variables used in lisp. String results are used without the quotes
     Lisp(String, String, Vec<(usize, usize)>),
-    /// Shell Command, use the output of command in the rendered String
+    /// Shell Command, use the output of command in the rendered String, see [`RenderOptions::fail_on_command_error`] for the commands that exit with non-zero status
     Cmd(Vec<TemplatePart>),
-    /// Multiple variables or [`TemplatePart`]s, use the first one that succeeds
+    /// Multiple variables or [`TemplatePart`]s, use the first one that succeeds, including its transformers
     Any(Vec<TemplatePart>),
+    /// Empty alternative of `??` inside [`TemplatePart::Any`], like `{name??other}`, the part before it is skipped if its value is empty
+    SkipEmpty,
 }
 
 lazy_static! {
@@ -354,10 +580,11 @@ impl TemplatePart {
         Self::Lit(part.to_string())
     }
     pub fn var(part: &str) -> Self {
-        if let Some((part, fstr)) = part.split_once(VAR_TRANSFORM_SEP_CHAR) {
-            Self::Var(part.to_string(), fstr.to_string())
+        if let Some(i) = find_unescaped(part, VAR_TRANSFORM_SEP_CHAR) {
+            let fstr = &part[(i + VAR_TRANSFORM_SEP_CHAR.len_utf8())..];
+            Self::Var(remove_escapes(&part[..i]).to_string(), fstr.to_string())
         } else {
-            Self::Var(part.to_string(), "".to_string())
+            Self::Var(remove_escapes(part).to_string(), "".to_string())
         }
     }
 
@@ -367,12 +594,18 @@ impl TemplatePart {
         } else {
             (part.to_string(), "".to_string())
         };
-        let variables = part
-            .match_indices("(st+")
+        let variables = Self::lisp_var_ranges(&part);
+        Self::Lisp(part, fstr, variables)
+    }
+
+    /// Start..end of the variables used in the lisp expression with
+    /// the functions like `(st+var 'name)`
+    fn lisp_var_ranges(expr: &str) -> Vec<(usize, usize)> {
+        expr.match_indices("(st+")
             .filter_map(|(loc, _)| {
-                let end = Self::find_end(')', &part, loc + 1).ok()?;
-                part[loc..end].find(' ').map(|s| {
-                    let p = &part[(s + 1 + loc)..end];
+                let end = Self::find_end(')', expr, loc + 1).ok()?;
+                expr[loc..end].find(' ').map(|s| {
+                    let p = &expr[(s + 1 + loc)..end];
                     if p.starts_with('"') {
                         (s + 2 + loc, end - 1)
                     } else if p.starts_with('\'') {
@@ -382,8 +615,32 @@ impl TemplatePart {
                     }
                 })
             })
-            .collect();
-        Self::Lisp(part, fstr, variables)
+            .collect()
+    }
+
+    /// Variables for evaluating the lisp expression, the values in
+    /// `vars` of the variables used in it replace the ones in the
+    /// [`RenderOptions::variables`]
+    pub(crate) fn lisp_variables<'a>(
+        expr: &str,
+        vars: &dyn Variables,
+        op: &'a RenderOptions,
+    ) -> Cow<'a, HashMap<String, String>> {
+        let mut lisp_vars = Cow::Borrowed(&op.variables);
+        for (s, e) in Self::lisp_var_ranges(expr) {
+            // the default value can follow the name
+            let Some(name) = expr[s..e].split_whitespace().next() else {
+                continue;
+            };
+            if let Some(val) = vars.get(name) {
+                if op.variables.get(name).map(String::as_str) != Some(val.as_ref()) {
+                    lisp_vars
+                        .to_mut()
+                        .insert(name.to_string(), val.into_owned());
+                }
+            }
+        }
+        lisp_vars
     }
 
     pub fn time(part: &str) -> Self {
@@ -397,7 +654,7 @@ impl TemplatePart {
         } else if part.starts_with(LITERAL_VALUE_QUOTE_CHAR)
             && part.ends_with(LITERAL_VALUE_QUOTE_CHAR)
         {
-            Self::lit(&part[1..(part.len() - 1)])
+            Self::lit(&remove_escapes(&part[1..(part.len() - 1)]))
         } else if part.starts_with(TIME_FORMAT_CHAR) {
             Self::time(part)
         } else if part.starts_with(LISP_START_CHAR) {
@@ -420,11 +677,18 @@ impl TemplatePart {
     }
 
     pub fn maybe_any(part: &str) -> Self {
-        if part.contains(OPTIONAL_RENDER_CHAR) {
-            let parts = part
-                .split(OPTIONAL_RENDER_CHAR)
-                .map(|s| s.trim())
-                .map(Self::maybe_var)
+        if find_unescaped(part, OPTIONAL_RENDER_CHAR).is_some() {
+            let parts: Vec<&str> = split_unescaped(part, OPTIONAL_RENDER_CHAR);
+            let parts = parts
+                .iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    if s.is_empty() && i > 0 && i + 1 < parts.len() {
+                        Self::SkipEmpty
+                    } else {
+                        Self::maybe_var(s.trim())
+                    }
+                })
                 .collect();
 
             Self::any(parts)
@@ -447,7 +711,19 @@ impl TemplatePart {
             );
         }
         let mut nest: Vec<char> = Vec::new();
-        for (i, c) in templ[offset..].chars().enumerate() {
+        let mut escaped = false;
+        for (i, c) in templ[offset..].char_indices() {
+            if escaped {
+                escaped = false;
+                continue;
+            } else if c == ESCAPE_CHAR {
+                escaped = true;
+                continue;
+            }
+            // anything else inside the quotes is taken as it is
+            if nest.last() == Some(&'"') && c != '"' {
+                continue;
+            }
             if c == end && nest.is_empty() {
                 return Ok(offset + i);
             } else if TEMPLATE_PAIRS_START.contains(&c) {
@@ -481,14 +757,23 @@ impl TemplatePart {
         ))
     }
     pub fn tokenize(templ: &str) -> Result<Vec<Self>, errors::RenderTemplateError> {
-        let mut parts: Vec<TemplatePart> = Vec::new();
+        Self::tokenize_spans(templ).map(|parts| parts.into_iter().map(|(_, p)| p).collect())
+    }
+
+    /// Same as [`TemplatePart::tokenize`] but with the byte range of
+    /// the source each part is parsed from, escaped characters
+    /// include the escape character in their range.
+    pub fn tokenize_spans(
+        templ: &str,
+    ) -> Result<Vec<(Range<usize>, Self)>, errors::RenderTemplateError> {
+        let mut parts: Vec<(Range<usize>, TemplatePart)> = Vec::new();
         let mut last = 0usize;
         let mut i = 0usize;
         let mut escape = false;
         while i < templ.len() {
             if templ[i..].starts_with(ESCAPE_CHAR) && !escape {
                 if i > last {
-                    parts.push(Self::lit(&templ[last..i]));
+                    parts.push((last..i, Self::lit(&templ[last..i])));
                 }
                 i += 1;
                 last = i;
@@ -496,7 +781,7 @@ impl TemplatePart {
                 continue;
             }
             if escape {
-                parts.push(Self::lit(&templ[i..(i + 1)]));
+                parts.push(((i - 1)..(i + 1), Self::lit(&templ[i..(i + 1)])));
                 last = i + 1;
                 i += 1;
                 escape = false;
@@ -505,45 +790,266 @@ impl TemplatePart {
             if templ[i..].starts_with("$(") {
                 let end = Self::find_end(')', templ, i + 2)?;
                 if i > last {
-                    parts.push(Self::lit(&templ[last..i]));
+                    parts.push((last..i, Self::lit(&templ[last..i])));
                 }
                 last = end + 1;
-                parts.push(Self::parse_cmd(&templ[(i + 2)..end])?);
+                parts.push((i..(end + 1), Self::parse_cmd(&templ[(i + 2)..end])?));
                 i = end;
             } else if templ[i..].starts_with("=(") {
                 let end = Self::find_end(')', templ, i + 2)?;
                 if i > last {
-                    parts.push(Self::lit(&templ[last..i]));
+                    parts.push((last..i, Self::lit(&templ[last..i])));
                 }
                 last = end + 1;
                 // need to include the found ')' for lisp expr to be valid
-                parts.push(Self::lisp(&templ[(i + 1)..=end]));
+                parts.push((i..(end + 1), Self::lisp(&templ[(i + 1)..=end])));
                 i = end;
             } else if templ[i..].starts_with('{') {
                 let end = Self::find_end('}', templ, i + 1)?;
                 if i > last {
-                    parts.push(Self::lit(&templ[last..i]));
+                    parts.push((last..i, Self::lit(&templ[last..i])));
                 }
                 last = end + 1;
-                parts.push(Self::maybe_any(&templ[(i + 1)..end]));
+                let part = Self::maybe_any(&templ[(i + 1)..end]);
+                part.check_time_formats()?;
+                parts.push((i..(end + 1), part));
                 i = end;
             } else if templ[i..].starts_with('"') {
                 let end = Self::find_end('"', templ, i + 1)?;
                 if i > last {
-                    parts.push(Self::lit(&templ[last..i]));
+                    parts.push((last..i, Self::lit(&templ[last..i])));
                 }
                 last = end + 1;
-                parts.push(Self::lit(&templ[(i + 1)..end]));
+                parts.push((i..(end + 1), Self::lit(&templ[(i + 1)..end])));
                 i = end;
             }
             i += 1;
         }
         if templ.len() > last {
-            parts.push(Self::lit(&templ[last..]));
+            parts.push((last..templ.len(), Self::lit(&templ[last..])));
         }
         Ok(parts)
     }
 
+    /// Checks all the transformers used in this part exist and have
+    /// the correct number of arguments, see [`transformers::check_transformers`]
+    pub fn check_transformers(&self) -> Result<(), errors::TransformerError> {
+        match self {
+            TemplatePart::Var(v, f) => transformers::check_transformers(v, f),
+            TemplatePart::Lisp(e, f, _) => transformers::check_transformers(e, f),
+            TemplatePart::Any(parts) | TemplatePart::Cmd(parts) => {
+                parts.iter().try_for_each(|p| p.check_transformers())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the programs run by the commands in this part (including
+    /// the nested ones) are in the `allowed` list, see
+    /// [`Template::validate_commands`]
+    pub fn validate_commands(&self, allowed: &[&str]) -> Result<(), errors::RenderTemplateError> {
+        match self {
+            TemplatePart::Cmd(parts) => {
+                let (cmd_parts, stdin) = match parts.as_slice() {
+                    [rest @ .., TemplatePart::Lit(l), input] if l.ends_with(CMD_STDIN_MARKER) => {
+                        let lit = Self::lit(&l[..(l.len() - CMD_STDIN_MARKER.len())]);
+                        (rest.iter().chain([&lit]).cloned().collect(), Some(input))
+                    }
+                    _ => (parts.clone(), None),
+                };
+                // the parts that aren't literal are unknown before rendering
+                let cmd: String = cmd_parts
+                    .iter()
+                    .map(|p| match p {
+                        TemplatePart::Lit(l) => l.to_string(),
+                        TemplatePart::Var(v, _) => format!("{{{v}}}"),
+                        _ => "{...}".to_string(),
+                    })
+                    .collect();
+                if let Some(prog) = Self::command_programs(&cmd)
+                    .into_iter()
+                    .find(|p| !allowed.contains(p))
+                {
+                    return Err(errors::RenderTemplateError::CommandNotAllowed(
+                        cmd.clone(),
+                        prog.to_string(),
+                    ));
+                }
+                parts
+                    .iter()
+                    .chain(stdin)
+                    .try_for_each(|p| p.validate_commands(allowed))
+            }
+            TemplatePart::Any(parts) => parts.iter().try_for_each(|p| p.validate_commands(allowed)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Programs run by the shell command, the first word of each of
+    /// the commands separated by pipes, `;`, `&&`, `||`, `&` or new
+    /// lines, skipping the environment variable assignments
+    fn command_programs(cmd: &str) -> Vec<&str> {
+        let mut commands = Vec::new();
+        let mut quote: Option<char> = None;
+        let mut start = 0;
+        for (i, c) in cmd.char_indices() {
+            match (quote, c) {
+                (Some(q), c) if q == c => quote = None,
+                (Some(_), _) => (),
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '|' | ';' | '&' | '\n' | '(' | ')') => {
+                    commands.push(&cmd[start..i]);
+                    start = i + 1;
+                }
+                _ => (),
+            }
+        }
+        commands.push(&cmd[start..]);
+        commands
+            .into_iter()
+            .filter_map(|c| {
+                c.split_whitespace()
+                    .find(|w| !w.contains('=') || w.starts_with('='))
+            })
+            .map(|p| p.trim_matches(|c| c == '"' || c == '\''))
+            .collect()
+    }
+
+    /// Parse a [`&str`] that has exactly one [`TemplatePart`], errors
+    /// if there are other parts (like literal strings) around it.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{Render, RenderOptions, TemplatePart};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let part = TemplatePart::parse_one("{name:case(up)}")?;
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "world".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     assert_eq!(part.render(&options)?, "WORLD");
+    ///     assert!(TemplatePart::parse_one("{name} there").is_err());
+    /// # Ok(())
+    /// # }
+    pub fn parse_one(part: &str) -> Result<Self, errors::RenderTemplateError> {
+        let mut parts = Self::tokenize(part)?;
+        if parts.len() == 1 {
+            Ok(parts.remove(0))
+        } else {
+            Err(errors::RenderTemplateError::InvalidFormat(
+                part.to_string(),
+                format!("Expected a single template part, found {}", parts.len()),
+            ))
+        }
+    }
+
+    /// Render the part with only the given variables and the default
+    /// for the other [`RenderOptions`]
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::TemplatePart;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let part = TemplatePart::Var("name".into(), "case(up)".into());
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "world".into());
+    ///     assert_eq!(part.render_with_vars(&vars)?, "WORLD");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_with_vars(&self, vars: &HashMap<String, String>) -> Result<String, Error> {
+        self.render(&RenderOptions {
+            variables: vars.clone(),
+            ..Default::default()
+        })
+    }
+
+    /// Render the part with the values from the [`Variables`], see
+    /// [`Template::render_with`]
+    pub fn render_with<V: Variables + ?Sized>(
+        &self,
+        vars: &V,
+        op: &RenderOptions,
+    ) -> Result<String, Error> {
+        let vars = LayeredVariables {
+            vars,
+            base: &op.variables,
+        };
+        self.render_nested(op, &vars, 0)
+    }
+
+    /// Checks the date time format is valid for chrono, as the
+    /// invalid ones can't be rendered
+    fn check_time_format(fmt: &str) -> Result<(), errors::RenderTemplateError> {
+        if StrftimeItems::new(fmt).any(|i| i == Item::Error) {
+            Err(errors::RenderTemplateError::InvalidFormat(
+                fmt.to_string(),
+                "Invalid date time format".to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks the date time formats in this part and the ones nested inside
+    fn check_time_formats(&self) -> Result<(), errors::RenderTemplateError> {
+        match self {
+            TemplatePart::Time(t) => Self::check_time_format(t),
+            TemplatePart::Any(parts) | TemplatePart::Cmd(parts) => {
+                parts.iter().try_for_each(|p| p.check_time_formats())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Merges the consecutive literals inside the commands, see [`Template::minify`]
+    fn minify(&self) -> Self {
+        match self {
+            TemplatePart::Cmd(parts) => {
+                let mut merged: Vec<TemplatePart> = Vec::with_capacity(parts.len());
+                for part in parts.iter().map(|p| p.minify()) {
+                    match (merged.last_mut(), part) {
+                        (Some(TemplatePart::Lit(last)), TemplatePart::Lit(l)) => last.push_str(&l),
+                        (_, part) => merged.push(part),
+                    }
+                }
+                TemplatePart::Cmd(merged)
+            }
+            TemplatePart::Any(parts) => {
+                TemplatePart::Any(parts.iter().map(|p| p.minify()).collect())
+            }
+            p => p.clone(),
+        }
+    }
+
+    /// Whether this part or any part nested inside it satisfies the predicate
+    fn contains_part(&self, pred: fn(&TemplatePart) -> bool) -> bool {
+        pred(self)
+            || match self {
+                TemplatePart::Any(parts) | TemplatePart::Cmd(parts) => {
+                    parts.iter().any(|p| p.contains_part(pred))
+                }
+                _ => false,
+            }
+    }
+
+    /// Only the literal strings in this part, including the ones nested inside
+    pub fn literal_text(&self) -> String {
+        match self {
+            TemplatePart::Lit(l) => l.to_string(),
+            TemplatePart::Any(parts) | TemplatePart::Cmd(parts) => {
+                parts.iter().map(|p| p.literal_text()).collect()
+            }
+            _ => String::new(),
+        }
+    }
+
     pub fn variables(&self) -> Vec<&str> {
         match self {
             TemplatePart::Var(v, _) => vec![v.as_str()],
@@ -553,24 +1059,101 @@ impl TemplatePart {
             _ => vec![],
         }
     }
+
+    /// Write the visualization, escape codes are only written when `color` is true
+    fn visualize<W: Write>(&self, w: &mut W, color: bool) -> io::Result<()> {
+        let esc = |code: &'static str| if color { code } else { "" };
+        match self {
+            Self::Lit(s) => write!(w, "{}", s)?,
+            Self::Var(s, sf) => write!(w, "{}", {
+                if sf.is_empty() {
+                    paint(s.on_blue(), color)
+                } else {
+                    paint(
+                        format!("{}:{}", s, paint(sf.on_bright_blue(), color)).on_blue(),
+                        color,
+                    )
+                }
+            })?,
+            Self::Time(s) => write!(w, "{}", paint(s.on_yellow(), color))?,
+            Self::SkipEmpty => (),
+            Self::Lisp(expr, sf, vars) => {
+                let mut last = 0;
+                for (s, e) in vars {
+                    write!(w, "{}", paint(expr[last..*s].on_purple(), color))?;
+                    write!(w, "{}", paint(expr[*s..*e].on_blue(), color))?;
+                    last = *e;
+                }
+                write!(w, "{}", paint(expr[last..expr.len()].on_purple(), color))?;
+                if !sf.is_empty() {
+                    write!(w, "{}", paint(format!(":{}", sf).on_bright_purple(), color))?;
+                }
+            }
+            Self::Cmd(v) => {
+                // overline; so the literal values are detected
+                write!(w, "{}", esc("\x1B[53m"))?;
+                write!(w, "{}", paint("$(".on_red(), color))?;
+                for p in v {
+                    write!(w, "{}", esc("\x1B[53m"))?;
+                    p.visualize(w, color)?;
+                }
+                write!(w, "{}", esc("\x1B[53m"))?;
+                write!(w, "{}", paint(")".on_red(), color))?;
+            }
+            Self::Any(v) => {
+                for p in &v[..(v.len() - 1)] {
+                    // underline; so the literal values are detected
+                    write!(w, "{}", esc("\x1B[4m"))?;
+                    p.visualize(w, color)?;
+                    write!(w, "{}", esc("\x1B[4m"))?;
+                    write!(
+                        w,
+                        "{}",
+                        paint(OPTIONAL_RENDER_CHAR.to_string().on_yellow(), color)
+                    )?;
+                }
+                write!(w, "{}", esc("\x1B[4m"))?;
+                v.iter().last().unwrap().visualize(w, color)?;
+                write!(w, "{}", esc("\x1B[0m"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Colored text for visualization, or just the text if `color` is false
+fn paint(s: ColoredString, color: bool) -> String {
+    if color {
+        s.to_string()
+    } else {
+        s.input
+    }
 }
-impl ToString for TemplatePart {
-    fn to_string(&self) -> String {
+
+impl fmt::Display for TemplatePart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Lit(s) => format!("{0}{1}{0}", LITERAL_VALUE_QUOTE_CHAR, s),
-            Self::Var(s, _) => s.to_string(),
-            Self::Time(s) => s.to_string(),
-            Self::Lisp(e, _, _) => e.to_string(),
-            Self::Cmd(v) => v
-                .iter()
-                .map(|p| p.to_string())
-                .collect::<Vec<String>>()
-                .join(""),
-            Self::Any(v) => v
-                .iter()
-                .map(|p| p.to_string())
-                .collect::<Vec<String>>()
-                .join(OPTIONAL_RENDER_CHAR.to_string().as_str()),
+            Self::Lit(s) => write!(f, "{0}{1}{0}", LITERAL_VALUE_QUOTE_CHAR, s),
+            Self::Var(s, _) => write!(f, "{s}"),
+            Self::Time(s) => write!(f, "{s}"),
+            Self::Lisp(e, _, _) => write!(f, "{e}"),
+            Self::SkipEmpty => Ok(()),
+            Self::Cmd(v) => write!(
+                f,
+                "{}",
+                v.iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<String>>()
+                    .join("")
+            ),
+            Self::Any(v) => write!(
+                f,
+                "{}",
+                v.iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<String>>()
+                    .join(OPTIONAL_RENDER_CHAR.to_string().as_str())
+            ),
         }
     }
 }
@@ -593,6 +1176,7 @@ impl ToString for TemplatePart {
 ///             wd: PathBuf::from("."),
 ///             variables: vars,
 ///             shell_commands: true,
+///             ..Default::default()
 ///         })
 ///         .unwrap();
 ///     assert_eq!(rendered, "hello John. You're 132.3kg");
@@ -602,6 +1186,10 @@ impl ToString for TemplatePart {
 pub struct Template {
     original: String,
     parts: Vec<TemplatePart>,
+    spans: Vec<Range<usize>>,
+    /// Text of the template made of many literal parts, so
+    /// [`Template::render_cow`] can borrow it
+    literal: Option<String>,
 }
 
 impl std::convert::AsRef<str> for Template {
@@ -630,451 +1218,2973 @@ impl Template {
     /// # Ok(())
     /// }
     pub fn parse_template(templ_str: &str) -> Result<Template, Error> {
-        let template_parts = TemplatePart::tokenize(templ_str)?;
+        let (spans, template_parts): (_, Vec<TemplatePart>) =
+            TemplatePart::tokenize_spans(templ_str)?.into_iter().unzip();
         Ok(Self {
             original: templ_str.to_string(),
+            literal: Self::literal_parts_text(&template_parts),
             parts: template_parts,
+            spans,
         })
     }
 
+    /// Merged text of the parts if there are many and all of them are
+    /// literals, like the ones with escapes
+    fn literal_parts_text(parts: &[TemplatePart]) -> Option<String> {
+        if parts.len() < 2 {
+            return None;
+        }
+        parts
+            .iter()
+            .map(|p| match p {
+                TemplatePart::Lit(l) => Some(l.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn parts(&self) -> &Vec<TemplatePart> {
         &self.parts
     }
 
+    /// Byte ranges of the original template string each of the
+    /// [`TemplatePart`] was parsed from, useful to point at the
+    /// location of the parts in editors and error messages
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::{Template, TemplatePart};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name}!")?;
+    ///     let spans = templ.part_spans();
+    ///     assert_eq!(spans[1], (6..12, &TemplatePart::var("name")));
+    ///     assert_eq!(&templ.original()[spans[1].0.clone()], "{name}");
+    /// # Ok(())
+    /// # }
+    pub fn part_spans(&self) -> Vec<(Range<usize>, &TemplatePart)> {
+        self.spans.iter().cloned().zip(self.parts.iter()).collect()
+    }
+
     pub fn original(&self) -> &str {
         &self.original
     }
 
-    /// Concatenated String if [`Template`] is only literal strings
-    pub fn lit(&self) -> Option<String> {
-        let mut lit = String::new();
-        for part in &self.parts {
-            if let TemplatePart::Lit(l) = part {
-                lit.push_str(l);
-            } else {
-                return None;
+    /// Names of all the variables used in the [`Template`] in the
+    /// order they first appear, including the ones inside the
+    /// optional parts, commands and lisp expressions
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::Template;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("{name} {nick?name?} $(echo {age})")?;
+    ///     assert_eq!(templ.variables(), ["name", "nick", "age"]);
+    /// # Ok(())
+    /// # }
+    pub fn variables(&self) -> Vec<&str> {
+        let mut vars: Vec<&str> = Vec::new();
+        for v in self.parts.iter().flat_map(|p| p.variables()) {
+            if !vars.contains(&v) {
+                vars.push(v);
             }
         }
-        Some(lit)
-    }
-}
-
-/// Provides the function to render the object with [`RenderOptions`] into [`String`]
-pub trait Render {
-    fn render(&self, op: &RenderOptions) -> Result<String, Error>;
-
-    fn print(&self);
-}
-
-/// Options for the [`Template`] to render into [`String`]
-#[derive(Default, Debug, Clone)]
-pub struct RenderOptions {
-    /// Working Directory for the Shell Commands
-    pub wd: PathBuf,
-    /// Variables to use for the template
-    pub variables: HashMap<String, String>,
-    /// Run Shell Commands for the output or not
-    pub shell_commands: bool,
-}
-
-impl RenderOptions {
-    pub fn render(&self, templ: &Template) -> Result<String, Error> {
-        templ.render(self)
+        vars
     }
 
-    /// Makes a [`RenderIter<'a>`] that can generate incremented strings from the given [`Template`] and the [`RenderOptions`]. The Iterator will have `-N` appended where N is the number representing the number of instance.
+    /// Names of the variables the [`Template`] can not be rendered
+    /// without, the ones inside the optional parts (`{a?b}`) and the
+    /// lisp expressions are left out as they can have fallbacks
+    ///
+    /// Along with [`Template::parse_template`] and
+    /// [`Template::variables`], this is what a compile time macro
+    /// needs to generate a typed struct for a template.
     ///
     /// ```rust
     /// # use std::error::Error;
-    /// # use std::collections::HashMap;
-    /// # use string_template_plus::{Render, RenderOptions, Template};
+    /// # use string_template_plus::Template;
     /// #
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    ///     let templ = Template::parse_template("hello {name}").unwrap();
-    ///     let mut vars: HashMap<String, String> = HashMap::new();
-    ///     vars.insert("name".into(), "world".into());
-    ///     let options = RenderOptions {
-    ///         variables: vars,
-    ///         ..Default::default()
-    ///     };
-    ///     let mut names = options.render_iter(&templ);
-    ///     assert_eq!("hello world-1", names.next().unwrap());
-    ///     assert_eq!("hello world-2", names.next().unwrap());
-    ///     assert_eq!("hello world-3", names.next().unwrap());
+    ///     let templ = Template::parse_template("{name} {nick?name?} $(echo {age})")?;
+    ///     assert_eq!(templ.required_variables(), ["name", "age"]);
     /// # Ok(())
     /// # }
-    pub fn render_iter<'a>(&'a self, templ: &'a Template) -> RenderIter<'a> {
-        RenderIter {
-            template: templ,
-            options: self,
-            count: 0,
+    pub fn required_variables(&self) -> Vec<&str> {
+        fn required<'a>(part: &'a TemplatePart, vars: &mut Vec<&'a str>) {
+            match part {
+                TemplatePart::Var(v, _) if !vars.contains(&v.as_str()) => vars.push(v),
+                TemplatePart::Cmd(parts) => parts.iter().for_each(|p| required(p, vars)),
+                _ => (),
+            }
         }
+        let mut vars = Vec::new();
+        self.parts.iter().for_each(|p| required(p, &mut vars));
+        vars
     }
-}
 
-/// Render option with [`Iterator`] support. You can use this to get
-/// incremented render results. It'll add `-N` to the render
-/// [`Template`] where `N` is the count (1,2,3...). It can be useful
-/// to make files with a given template.
-///
-/// ```rust
-/// # use std::error::Error;
-/// # use std::collections::HashMap;
-/// # use string_template_plus::{Render, RenderOptions, RenderIter, Template};
-/// #
-/// # fn main() -> Result<(), Box<dyn Error>> {
-///     let templ = Template::parse_template("hello {name}").unwrap();
-///     let mut vars: HashMap<String, String> = HashMap::new();
-///     vars.insert("name".into(), "world".into());
-///     let options = RenderOptions {
-///         variables: vars,
-///         ..Default::default()
-///     };
-///     let mut names = RenderIter::new(&templ, &options);
-///     assert_eq!("hello world-1", names.next().unwrap());
-///     assert_eq!("hello world-2", names.next().unwrap());
-///     assert_eq!("hello world-3", names.next().unwrap());
-/// # Ok(())
-/// # }
-#[derive(Debug, Clone)]
-pub struct RenderIter<'a> {
-    template: &'a Template,
-    options: &'a RenderOptions,
-    count: usize,
-}
-
-impl<'a> RenderIter<'a> {
-    /// Creates a new [`RenderIter<'a>`] object
-    pub fn new(template: &'a Template, options: &'a RenderOptions) -> Self {
+    /// Merges the consecutive literal parts (including the ones
+    /// inside the commands) into one, escapes and quotes split the
+    /// literal text into many small parts while parsing. The rendered
+    /// text is the same, but with fewer parts to render.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::{Render, RenderOptions, Template, TemplatePart};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("a\\{b\\} {name?}")?;
+    ///     assert_eq!(templ.parts().len(), 6);
+    ///     let mini = templ.minify();
+    ///     assert_eq!(mini.parts().len(), 2);
+    ///     assert_eq!(mini.parts()[0], TemplatePart::lit("a{b} "));
+    ///     assert_eq!(mini.render(&RenderOptions::default())?, "a{b} ");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn minify(&self) -> Template {
+        let mut parts: Vec<TemplatePart> = Vec::with_capacity(self.parts.len());
+        let mut spans: Vec<Range<usize>> = Vec::with_capacity(self.spans.len());
+        for (span, part) in self.spans.iter().zip(&self.parts) {
+            match (parts.last_mut(), part.minify()) {
+                (Some(TemplatePart::Lit(last)), TemplatePart::Lit(l)) => {
+                    last.push_str(&l);
+                    spans.last_mut().unwrap().end = span.end;
+                }
+                (_, part) => {
+                    parts.push(part);
+                    spans.push(span.clone());
+                }
+            }
+        }
         Self {
-            template,
-            options,
-            count: 0,
+            original: self.original.clone(),
+            literal: Self::literal_parts_text(&parts),
+            parts,
+            spans,
         }
     }
-}
 
-impl<'a> Iterator for RenderIter<'a> {
-    type Item = String;
-    fn next(&mut self) -> Option<String> {
-        self.template.render(self.options).ok().map(|t| {
-            self.count += 1;
-            format!("{}-{}", t, self.count)
-        })
+    /// Checks the transformers used in the [`Template`] without
+    /// rendering it, so mistakes can be caught before rendering.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::Template;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name:case(up)}")?;
+    ///     assert!(templ.check_transformers().is_ok());
+    ///     let templ = Template::parse_template("hello {name:nope()}")?;
+    ///     assert!(templ.check_transformers().is_err());
+    /// # Ok(())
+    /// # }
+    pub fn check_transformers(&self) -> Result<(), errors::TransformerError> {
+        self.parts.iter().try_for_each(|p| p.check_transformers())
     }
-}
 
-impl Render for TemplatePart {
-    fn render(&self, op: &RenderOptions) -> Result<String, Error> {
-        match self {
-            TemplatePart::Lit(l) => Ok(l.to_string()),
-            TemplatePart::Var(v, f) => op
-                .variables
-                .get(v)
-                .ok_or(errors::RenderTemplateError::VariableNotFound(v.to_string()))
-                .map(|s| -> Result<String, Error> { Ok(transformers::apply_tranformers(s, f)?) })?,
-            TemplatePart::Time(t) => Ok(Local::now().format(t).to_string()),
-            TemplatePart::Lisp(e, f, _) => Ok(transformers::apply_tranformers(
-                &lisp::calculate(&op.variables, e)?,
-                f,
-            )?),
-            TemplatePart::Cmd(c) => {
-                let cmd = c.render(op)?;
-                if op.shell_commands {
-                    cmd_output(&cmd, &op.wd)
-                } else {
-                    Ok(format!("$({cmd})"))
-                }
+    /// Checks that the commands in the [`Template`] only run the
+    /// programs in the `allowed` list, so the templates can be
+    /// restricted before enabling [`RenderOptions::shell_commands`].
+    ///
+    /// The program is the first word of each command in a pipeline or
+    /// a list (`|`, `;`, `&&`, `||`, `&`, new lines and subshells), the
+    /// ones that come from the variables are never allowed. The
+    /// arguments are not checked, so allowing programs that run other
+    /// programs like `sh -c`, `xargs`, `env` or `find -exec` allows
+    /// anything; keep them out of the list. Backticks, and the `$()`
+    /// inside the shell quotes (`\"`) are not checked either. The values
+    /// of the variables are put in the commands as they are, so this
+    /// only holds when the values are trusted.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::Template;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("$(echo {name} | tr a-z A-Z)")?;
+    ///     assert!(templ.validate_commands(&["echo", "tr"]).is_ok());
+    ///     assert!(templ.validate_commands(&["echo"]).is_err());
+    /// # Ok(())
+    /// # }
+    pub fn validate_commands(&self, allowed: &[&str]) -> Result<(), errors::RenderTemplateError> {
+        self.parts
+            .iter()
+            .try_for_each(|p| p.validate_commands(allowed))
+    }
+
+    /// Whether the [`Template`] has any Shell Commands, including the
+    /// nested ones. Useful to decide if the rendered text should be
+    /// cached, as commands are expensive to run.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::Template;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("{name} $(echo {%Y})")?;
+    ///     assert!(templ.has_commands());
+    ///     assert!(templ.has_time());
+    ///     assert!(!templ.has_lisp());
+    /// # Ok(())
+    /// # }
+    pub fn has_commands(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|p| p.contains_part(|p| matches!(p, TemplatePart::Cmd(_))))
+    }
+
+    /// Whether the [`Template`] has any lisp expressions, including the nested ones
+    pub fn has_lisp(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|p| p.contains_part(|p| matches!(p, TemplatePart::Lisp(..))))
+    }
+
+    /// Whether the [`Template`] has any date time formats, including
+    /// the nested ones, which makes the rendered text change with time
+    pub fn has_time(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|p| p.contains_part(|p| matches!(p, TemplatePart::Time(_))))
+    }
+
+    /// Concatenated String if [`Template`] is only literal strings
+    pub fn lit(&self) -> Option<String> {
+        let mut lit = String::new();
+        for part in &self.parts {
+            if let TemplatePart::Lit(l) = part {
+                lit.push_str(l);
+            } else {
+                return None;
             }
-            TemplatePart::Any(a) => a.iter().find_map(|p| p.render(op).ok()).ok_or(
-                errors::RenderTemplateError::AllVariablesNotFound(
-                    a.iter().map(|p| p.to_string()).collect(),
-                )
-                .into(),
-            ),
         }
+        Some(lit)
     }
-    /// Visualize what has been parsed so it's easier to debug
-    fn print(&self) {
-        match self {
-            Self::Lit(s) => print!("{}", s),
-            Self::Var(s, sf) => print!("{}", {
-                if sf.is_empty() {
-                    s.on_blue()
-                } else {
-                    format!("{}:{}", s, sf.on_bright_blue()).on_blue()
+
+    /// Render the [`Template`], or the `fallback` template if it fails.
+    /// The error is only given if the `fallback` fails as well.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name}")?;
+    ///     let fallback = Template::parse_template("hello there")?;
+    ///     assert_eq!(templ.render_or(&RenderOptions::default(), &fallback)?, "hello there");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_or(&self, op: &RenderOptions, fallback: &Template) -> Result<String, Error> {
+        self.render(op).or_else(|_| fallback.render(op))
+    }
+
+    /// Render the [`Template`] part by part, and stop at the first
+    /// part that fails. Gives the text rendered before the failure
+    /// along with the error, so you can see where it failed.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name}, welcome")?;
+    ///     let (rendered, err) = templ.render_partial(&RenderOptions::default());
+    ///     assert_eq!(rendered, "hello ");
+    ///     assert!(err.is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_partial(&self, op: &RenderOptions) -> (String, Option<Error>) {
+        let op = if op.command_vars.is_empty() {
+            Cow::Borrowed(op)
+        } else {
+            match op.with_command_vars() {
+                Ok(o) => Cow::Owned(o),
+                Err(e) => return (String::new(), Some(e)),
+            }
+        };
+        let _once = transformers::OnceScope::new();
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part.render(&op) {
+                Ok(s) => rendered.push_str(&s),
+                Err(e) => return (rendered, Some(e)),
+            }
+        }
+        (rendered, None)
+    }
+
+    /// Render the [`Template`] into multiple named outputs, the text
+    /// between `{>name}` and `{<}` goes to the output `name`, and the
+    /// rest goes to the default output with empty name. The sections
+    /// can be nested, and the ones not closed end with the template.
+    /// The names can't have [`VAR_TRANSFORM_SEP_CHAR`] in them.
+    ///
+    /// The section markers are only understood by this method, they
+    /// are variables for the other ones.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("{>a.txt}hello{<}{>b.txt}world{<}")?;
+    ///     let streams = templ.render_streams(&RenderOptions::default())?;
+    ///     assert_eq!(streams["a.txt"], "hello");
+    ///     assert_eq!(streams["b.txt"], "world");
+    ///     assert_eq!(streams[""], "");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_streams(&self, op: &RenderOptions) -> Result<HashMap<String, String>, Error> {
+        let op = if op.command_vars.is_empty() {
+            Cow::Borrowed(op)
+        } else {
+            Cow::Owned(op.with_command_vars()?)
+        };
+        let _once = transformers::OnceScope::new();
+        let mut streams: HashMap<String, String> = HashMap::from([(String::new(), String::new())]);
+        let mut current: Vec<&str> = vec![""];
+        for part in &self.parts {
+            match part {
+                TemplatePart::Var(v, _) if v.starts_with(STREAM_START_CHAR) => {
+                    let name = &v[STREAM_START_CHAR.len_utf8()..];
+                    streams.entry(name.to_string()).or_default();
+                    current.push(name);
                 }
-            }),
-            Self::Time(s) => print!("{}", s.on_yellow()),
-            Self::Lisp(expr, sf, vars) => {
-                let mut last = 0;
-                for (s, e) in vars {
-                    print!("{}", expr[last..*s].on_purple());
-                    print!("{}", expr[*s..*e].on_blue());
-                    last = *e;
+                TemplatePart::Var(v, _) if v == &STREAM_END_CHAR.to_string() => {
+                    if current.len() == 1 {
+                        return Err(errors::RenderTemplateError::InvalidFormat(
+                            self.original.to_string(),
+                            "Stream closed without opening".to_string(),
+                        )
+                        .into());
+                    }
+                    current.pop();
                 }
-                print!("{}", expr[last..expr.len()].on_purple());
-                if !sf.is_empty() {
-                    print!("{}", format!(":{}", sf).on_bright_purple())
+                p => {
+                    let rendered = p.render(&op)?;
+                    let name = current.last().unwrap();
+                    streams.get_mut(*name).unwrap().push_str(&rendered);
                 }
             }
-            Self::Cmd(v) => {
-                // overline; so the literal values are detected
-                print!("\x1B[53m");
-                print!("{}", "$(".on_red());
-                v.iter().for_each(|p| {
-                    print!("\x1B[53m");
-                    p.print();
-                });
-                print!("\x1B[53m");
-                print!("{}", ")".on_red());
+        }
+        Ok(streams)
+    }
+
+    /// Render the [`Template`] and give the names of the variables
+    /// that were looked up while rendering, found or not. Variables
+    /// in the alternatives of the optional parts are not looked up
+    /// if a previous alternative renders. Variables read inside the
+    /// lisp expressions are not tracked.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::{HashMap, HashSet};
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {nickname?name}")?;
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "world".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     let (rendered, used) = templ.render_tracked(&options)?;
+    ///     assert_eq!(rendered, "hello world");
+    ///     assert_eq!(used, HashSet::from(["nickname".to_string(), "name".to_string()]));
+    /// # Ok(())
+    /// # }
+    pub fn render_tracked(&self, op: &RenderOptions) -> Result<(String, HashSet<String>), Error> {
+        let used = Arc::new(Mutex::new(HashSet::new()));
+        let mut options = op.clone();
+        let (tracker, handler) = (used.clone(), op.on_event.clone());
+        options.on_event = Some(EventHandler::new(move |e| {
+            if let RenderEvent::VariableResolved(name, _) | RenderEvent::VariableMissing(name) = e {
+                tracker.lock().unwrap().insert(name.to_string());
             }
-            Self::Any(v) => {
-                v[..(v.len() - 1)].iter().for_each(|p| {
-                    // underline; so the literal values are detected
-                    print!("\x1B[4m");
-                    p.print();
-                    print!("\x1B[4m");
-                    print!("{}", OPTIONAL_RENDER_CHAR.to_string().on_yellow());
-                });
-                print!("\x1B[4m");
-                v.iter().last().unwrap().print();
-                print!("\x1B[0m");
+            if let Some(h) = &handler {
+                (h.0)(e);
+            }
+        }));
+        let rendered = self.render(&options)?;
+        let used = used.lock().unwrap().clone();
+        Ok((rendered, used))
+    }
+
+    /// Render the [`Template`] as a JSON string, with the quotes, so
+    /// it can be embedded in JSON as a value
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name}")?;
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "\"world\"\n".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     assert_eq!(templ.render_json_string(&options)?, r#""hello \"world\"\n""#);
+    /// # Ok(())
+    /// # }
+    pub fn render_json_string(&self, op: &RenderOptions) -> Result<String, Error> {
+        let rendered = self.render(op)?;
+        let mut json = String::with_capacity(rendered.len() + 2);
+        json.push('"');
+        for c in rendered.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+                c => json.push(c),
             }
         }
+        json.push('"');
+        Ok(json)
     }
-}
 
-impl Render for Vec<TemplatePart> {
-    fn render(&self, op: &RenderOptions) -> Result<String, Error> {
-        self.iter()
-            .map(|p| p.render(op))
-            .collect::<Result<Vec<String>, Error>>()
-            .map(|v| v.join(""))
+    /// Render the [`Template`] borrowing the text when it only has
+    /// literal strings, so rendering constant templates doesn't
+    /// allocate. This includes the ones with escapes or quotes, their
+    /// text is merged once while parsing.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::borrow::Cow;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let options = RenderOptions::default();
+    ///     let templ = Template::parse_template("hello world")?;
+    ///     assert!(matches!(templ.render_cow(&options)?, Cow::Borrowed("hello world")));
+    ///     let templ = Template::parse_template("hello \\{world\\}")?;
+    ///     assert!(matches!(templ.render_cow(&options)?, Cow::Borrowed("hello {world}")));
+    /// # Ok(())
+    /// # }
+    pub fn render_cow<'a>(&'a self, op: &RenderOptions) -> Result<Cow<'a, str>, Error> {
+        match (self.parts.as_slice(), &self.literal) {
+            ([], _) => Ok(Cow::Borrowed("")),
+            ([TemplatePart::Lit(l)], _) | (_, Some(l)) => Ok(Cow::Borrowed(l)),
+            _ => self.render(op).map(Cow::Owned),
+        }
+    }
+
+    /// Render the [`Template`] into UTF-8 bytes, useful when the
+    /// output is written directly to a file or a socket.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let options = RenderOptions::default();
+    ///     let templ = Template::parse_template("hello world")?;
+    ///     assert_eq!(templ.render_bytes(&options)?, b"hello world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_bytes(&self, op: &RenderOptions) -> Result<Vec<u8>, Error> {
+        self.render_cow(op).map(|r| r.into_owned().into_bytes())
+    }
+
+    /// Parse and render the template read from `reader` into `writer`
+    /// a chunk at a time, without keeping the whole template in the
+    /// memory. The parts are rendered as soon as they are complete,
+    /// so only a single unfinished part is kept at a time. Anything
+    /// rendered before an error has already been written.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "world".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     let mut out: Vec<u8> = Vec::new();
+    ///     Template::render_stream("hello {name}".as_bytes(), &options, &mut out)?;
+    ///     assert_eq!(out, b"hello world");
+    ///     assert!(Template::render_stream("hello {name".as_bytes(), &options, &mut out).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_stream<R: Read, W: Write>(
+        mut reader: R,
+        op: &RenderOptions,
+        writer: &mut W,
+    ) -> Result<(), Error> {
+        let op = if op.command_vars.is_empty() {
+            Cow::Borrowed(op)
+        } else {
+            Cow::Owned(op.with_command_vars()?)
+        };
+        let _once = transformers::OnceScope::new();
+        let mut buf: Vec<u8> = Vec::new();
+        let mut chunk = vec![0u8; STREAM_CHUNK_SIZE];
+        loop {
+            let n = match reader.read(&mut chunk) {
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            buf.extend_from_slice(&chunk[..n]);
+            // a multi byte character can be split between the chunks
+            let valid = match std::str::from_utf8(&buf) {
+                Ok(_) => buf.len(),
+                Err(e) if n > 0 && e.error_len().is_none() => e.valid_up_to(),
+                Err(e) => return Err(e.into()),
+            };
+            let text = std::str::from_utf8(&buf[..valid])?;
+            let end = if n == 0 {
+                text.len()
+            } else {
+                TemplatePart::stream_boundary(text)
+            };
+            let rendered = TemplatePart::tokenize(&text[..end])?.render(&op)?;
+            writer.write_all(rendered.as_bytes())?;
+            buf.drain(..end);
+            if n == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Render the [`Template`] and compare it with the `expected`
+    /// text, giving a unified diff if they differ or the error if
+    /// rendering fails. `None` means the rendered text matches.
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{RenderOptions, Template};
+    ///     let templ = Template::parse_template("hello {name}\nbye").unwrap();
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "world".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     assert_eq!(templ.render_diff(&options, "hello world\nbye"), None);
+    ///     assert!(templ.render_diff(&options, "hello there\nbye").is_some());
+    /// ```
+    #[cfg(feature = "diff")]
+    pub fn render_diff(&self, op: &RenderOptions, expected: &str) -> Option<String> {
+        match self.render(op) {
+            Ok(rendered) if rendered == expected => None,
+            Ok(rendered) => Some(
+                similar::TextDiff::from_lines(expected, &rendered)
+                    .unified_diff()
+                    .header("expected", "rendered")
+                    .to_string(),
+            ),
+            Err(e) => Some(format!("Render failed: {e}")),
+        }
+    }
+
+    /// Concatenated literal strings of the [`Template`] ignoring all
+    /// other parts, unlike [`Template::lit`] it doesn't fail if there
+    /// are non-literal parts. Useful for full text indexing.
+    pub fn literal_text(&self) -> String {
+        self.parts.iter().map(|p| p.literal_text()).collect()
+    }
+
+    /// Visualize what has been parsed to the stdout without any
+    /// colors or escape codes, see [`Template::print_plain_to`]
+    pub fn print_plain(&self) {
+        self.print_plain_to(&mut io::stdout())
+            .expect("failed printing to stdout");
+    }
+
+    /// Write the visualization without any colors or escape codes,
+    /// useful for logs and terminals without ANSI support. The
+    /// [`Render::print_to`] also skips the colors when `NO_COLOR`
+    /// environment variable is set.
+    pub fn print_plain_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.parts.iter().try_for_each(|p| p.visualize(w, false))
+    }
+
+    /// Parse and render each line of the [`Template`] independently,
+    /// so an error in one line doesn't stop the other lines from
+    /// rendering. Template parts spanning multiple lines will fail to
+    /// parse in this mode.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{Render, RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name}\nbye {nickname}").unwrap();
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "world".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     let lines = templ.render_lines(&options);
+    ///     assert_eq!(lines[0].as_ref().unwrap(), "hello world");
+    ///     assert!(lines[1].is_err());
+    /// # Ok(())
+    /// # }
+    pub fn render_lines(&self, op: &RenderOptions) -> Vec<Result<String, Error>> {
+        let _once = transformers::OnceScope::new();
+        self.original
+            .lines()
+            .map(|line| Template::parse_template(line)?.render(op))
+            .collect()
+    }
+
+    /// Render the [`Template`] once for each of the rows of
+    /// variables, the variables in the row are added over the
+    /// variables in the base [`RenderOptions`]. The
+    /// [`RenderOptions::command_vars`] are run once for all the rows.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{Render, RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("{greeting} {name}").unwrap();
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("greeting".into(), "hello".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     let rows: Vec<HashMap<String, String>> = vec![
+    ///         HashMap::from([("name".into(), "world".into())]),
+    ///         HashMap::from([("name".into(), "there".into())]),
+    ///     ];
+    ///     let rendered = templ.render_all(&options, &rows);
+    ///     assert_eq!(rendered[0].as_ref().unwrap(), "hello world");
+    ///     assert_eq!(rendered[1].as_ref().unwrap(), "hello there");
+    /// # Ok(())
+    /// # }
+    pub fn render_all(
+        &self,
+        base: &RenderOptions,
+        rows: &[HashMap<String, String>],
+    ) -> Vec<Result<String, Error>> {
+        let mut op = match base.with_command_vars() {
+            Ok(op) => op,
+            Err(e) => return Self::row_errors(e, rows.len()),
+        };
+        rows.iter()
+            .map(|row| self.render_row(&mut op, row))
+            .collect()
+    }
+
+    /// Renders with the variables in the row inserted over the ones
+    /// in `op`, the replaced variables are put back afterwards
+    fn render_row(
+        &self,
+        op: &mut RenderOptions,
+        row: &HashMap<String, String>,
+    ) -> Result<String, Error> {
+        let replaced: Vec<(&String, Option<String>)> = row
+            .iter()
+            .map(|(k, v)| (k, op.variables.insert(k.clone(), v.clone())))
+            .collect();
+        let rendered = self.render(op);
+        for (k, old) in replaced {
+            match old {
+                Some(v) => op.variables.insert(k.clone(), v),
+                None => op.variables.remove(k),
+            };
+        }
+        rendered
+    }
+
+    /// Same error for all the rows when the variables common to them
+    /// couldn't be resolved
+    fn row_errors(e: Error, rows: usize) -> Vec<Result<String, Error>> {
+        let msg = format!("{e:#}");
+        (0..rows).map(|_| Err(Error::msg(msg.clone()))).collect()
+    }
+
+    /// Render the [`Template`] with the values from anything that
+    /// implements [`Variables`] (like a struct), looked up before the
+    /// [`RenderOptions::variables`]. The values are looked up while
+    /// rendering, and are also used by the lisp expressions and the
+    /// `lisp`, `showif` and `render` transformers.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name} =(st+var 'name)")?;
+    ///     let vars = HashMap::from([("name".to_string(), "world".to_string())]);
+    ///     assert_eq!(templ.render_with(&vars, &RenderOptions::default())?, "hello world world");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn render_with<V: Variables + ?Sized>(
+        &self,
+        vars: &V,
+        op: &RenderOptions,
+    ) -> Result<String, Error> {
+        let _once = transformers::OnceScope::new();
+        let op = if op.command_vars.is_empty() {
+            Cow::Borrowed(op)
+        } else {
+            Cow::Owned(op.with_command_vars()?)
+        };
+        self.parts
+            .iter()
+            .map(|p| p.render_with(vars, &op))
+            .collect()
+    }
+
+    /// Same as [`Template::render_all`] but renders the rows in
+    /// parallel using [`rayon`], the results are in the same order as
+    /// the rows.
+    #[cfg(feature = "rayon")]
+    pub fn render_all_parallel(
+        &self,
+        base: &RenderOptions,
+        rows: &[HashMap<String, String>],
+    ) -> Vec<Result<String, Error>> {
+        let op = match base.with_command_vars() {
+            Ok(op) => op,
+            Err(e) => return Self::row_errors(e, rows.len()),
+        };
+        rows.par_iter()
+            .map_with(op, |op, row| self.render_row(op, row))
+            .collect()
+    }
+}
+
+/// Provides the function to render the object with [`RenderOptions`] into [`String`]
+pub trait Render {
+    fn render(&self, op: &RenderOptions) -> Result<String, Error>;
+
+    /// Write the visualization of what has been parsed into the
+    /// given writer, with colors to distinguish the parts. Writes the
+    /// text rendered with the default [`RenderOptions`] if it's not
+    /// implemented, the types in this crate write the colored parts.
+    fn print_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let text = self
+            .render(&RenderOptions::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        write!(w, "{text}")
+    }
+
+    /// Visualize what has been parsed to the stdout, see [`Render::print_to`]
+    fn print(&self) {
+        self.print_to(&mut io::stdout())
+            .expect("failed printing to stdout");
+    }
+}
+
+/// Source of the variable values to render the [`Template`] with, see
+/// [`Template::render_with`]. Implement it for your own types to
+/// render them without making a [`HashMap`] of their values first.
+pub trait Variables {
+    /// Value of the variable with the `name`, `None` if it's missing
+    fn get(&self, name: &str) -> Option<Cow<'_, str>>;
+}
+
+impl<S: std::hash::BuildHasher> Variables for HashMap<String, String, S> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        HashMap::get(self, name).map(|v| Cow::Borrowed(v.as_str()))
+    }
+}
+
+/// Looks up the variables in `vars` first and then in `base`, see
+/// [`Template::render_with`]
+struct LayeredVariables<'a, V: ?Sized> {
+    vars: &'a V,
+    base: &'a HashMap<String, String>,
+}
+
+impl<V: Variables + ?Sized> Variables for LayeredVariables<'_, V> {
+    fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.vars
+            .get(name)
+            .or_else(|| HashMap::get(self.base, name).map(|v| Cow::Borrowed(v.as_str())))
+    }
+}
+
+/// Key for the nested value, dotted with the parent key if there is one
+#[cfg(any(feature = "json", feature = "toml"))]
+fn nested_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// Flattens the JSON value into the variables, see [`RenderOptions::variables_from_file`]
+#[cfg(feature = "json")]
+fn flatten_json(prefix: &str, value: &serde_json::Value, vars: &mut HashMap<String, String>) {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .for_each(|(k, v)| flatten_json(&nested_key(prefix, k), v, vars)),
+        Value::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| flatten_json(&nested_key(prefix, &i.to_string()), v, vars)),
+        Value::String(s) => {
+            vars.insert(prefix.to_string(), s.to_string());
+        }
+        Value::Null => {
+            vars.insert(prefix.to_string(), String::new());
+        }
+        v => {
+            vars.insert(prefix.to_string(), v.to_string());
+        }
+    }
+}
+
+/// Flattens the TOML value into the variables, see [`RenderOptions::variables_from_file`]
+#[cfg(feature = "toml")]
+fn flatten_toml(prefix: &str, value: &toml::Value, vars: &mut HashMap<String, String>) {
+    use toml::Value;
+    match value {
+        Value::Table(map) => map
+            .iter()
+            .for_each(|(k, v)| flatten_toml(&nested_key(prefix, k), v, vars)),
+        Value::Array(arr) => arr
+            .iter()
+            .enumerate()
+            .for_each(|(i, v)| flatten_toml(&nested_key(prefix, &i.to_string()), v, vars)),
+        Value::String(s) => {
+            vars.insert(prefix.to_string(), s.to_string());
+        }
+        v => {
+            vars.insert(prefix.to_string(), v.to_string());
+        }
+    }
+}
+
+/// Events that happen while rendering the [`Template`], passed to
+/// the [`EventHandler`] in [`RenderOptions::on_event`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderEvent<'a> {
+    /// Variable was found, with its name and value
+    VariableResolved(&'a str, &'a str),
+    /// Variable was not found
+    VariableMissing(&'a str),
+    /// Shell Command is going to be run
+    CommandRun(&'a str),
+    /// Lisp expression was evaluated, with its result
+    LispEvaluated(&'a str, &'a str),
+}
+
+/// Callback that observes the [`RenderEvent`]s during rendering
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use std::sync::{Arc, Mutex};
+/// # use string_template_plus::{EventHandler, Render, RenderEvent, RenderOptions, Template};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let templ = Template::parse_template("hello {nickname?name}").unwrap();
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("name".into(), "world".into());
+///     let events = Arc::new(Mutex::new(Vec::new()));
+///     let ev = events.clone();
+///     let options = RenderOptions {
+///         variables: vars,
+///         on_event: Some(EventHandler::new(move |e| ev.lock().unwrap().push(format!("{e:?}")))),
+///         ..Default::default()
+///     };
+///     templ.render(&options)?;
+///     assert_eq!(
+///         *events.lock().unwrap(),
+///         ["VariableMissing(\"nickname\")", "VariableResolved(\"name\", \"world\")"]
+///     );
+/// # Ok(())
+/// # }
+#[derive(Clone)]
+pub struct EventHandler(pub Arc<dyn Fn(RenderEvent) + Send + Sync>);
+
+impl EventHandler {
+    pub fn new<F: Fn(RenderEvent) + Send + Sync + 'static>(func: F) -> Self {
+        Self(Arc::new(func))
+    }
+}
+
+impl fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventHandler")
+    }
+}
+
+/// Options for the [`Template`] to render into [`String`]
+#[derive(Default, Debug, Clone)]
+pub struct RenderOptions {
+    /// Working Directory for the Shell Commands
+    pub wd: PathBuf,
+    /// Variables to use for the template
+    pub variables: HashMap<String, String>,
+    /// Run Shell Commands for the output or not
+    pub shell_commands: bool,
+    /// Callback to observe variable lookups, commands and lisp evaluations
+    pub on_event: Option<EventHandler>,
+    /// Maximum nesting depth of [`TemplatePart::Cmd`] and
+    /// [`TemplatePart::Any`] parts, [`MAX_RENDER_DEPTH`] if `None`
+    pub max_depth: Option<usize>,
+    /// Fail with [`errors::RenderTemplateError::CommandFailed`] when
+    /// a Shell Command exits with non-zero status, its output is used
+    /// as it is by default (like `$(grep foo file)` with no matches)
+    pub fail_on_command_error: bool,
+    /// Number of times to retry the Shell Commands that exit with
+    /// non-zero status, the last output is used if they still fail
+    /// unless [`RenderOptions::fail_on_command_error`] is set
+    pub command_retries: usize,
+    /// Time to wait before retrying a failed Shell Command
+    pub command_retry_delay: Duration,
+    /// Replace invalid UTF-8 in the output of Shell Commands instead of failing
+    pub command_output_lossy: bool,
+    /// Remove one trailing newline from the output of Shell Commands
+    /// like the shell does, the output is kept as it is by default
+    pub trim_command_output: bool,
+    /// Skip the variables with empty values in the optional parts,
+    /// like `{name?other}` renders `other` when `name` is empty
+    pub empty_is_missing: bool,
+    /// Transformers to use with their alias names like `{var:@name}`,
+    /// see [`transformers::expand_aliases`]
+    pub transformer_aliases: HashMap<String, String>,
+    /// Fail when the numeric transformers like `calc` and `f` give
+    /// `NaN` or infinite values instead of rendering them as text
+    pub reject_non_finite: bool,
+    /// Shell Commands to run once before rendering, their outputs are
+    /// used as the variables with the given names, see
+    /// [`RenderOptions::with_command_vars`]
+    pub command_vars: HashMap<String, String>,
+    /// Maximum time the lisp expressions and the `lisp` transformer
+    /// can take to evaluate, see [`lisp::evaluate_timeout`]. No limit
+    /// if `None`. The evaluation can't be stopped, so an expression
+    /// that times out keeps running in a detached thread until it
+    /// finishes, or for the rest of the process if it never does
+    pub lisp_timeout: Option<Duration>,
+    /// Transformers that are not allowed in the templates, they fail
+    /// with [`errors::TransformerError::DisabledTransformer`]. Use
+    /// it with `shell_commands` set to false for untrusted templates
+    pub disabled_transformers: HashSet<String>,
+    /// Render the placeholders in the transformer arguments with the
+    /// variables before applying them, like `{x:repl({from},{to})}`.
+    /// The values are used as single arguments even if they have
+    /// commas, and the placeholders can't have [`OPTIONAL_RENDER_CHAR`].
+    /// Escape the braces to use them literally like `\{x\}`
+    pub render_transformer_args: bool,
+    /// Time to use as the current time for the time formats like
+    /// `{%F}` and the `ago` transformer, the local time if `None`.
+    /// Useful to get the same output every time, like in the tests
+    pub now: Option<DateTime<Local>>,
+}
+
+impl RenderOptions {
+    pub fn render(&self, templ: &Template) -> Result<String, Error> {
+        templ.render(self)
+    }
+
+    /// Runs the commands in [`RenderOptions::command_vars`] and gives
+    /// the options with their outputs as variables, so the commands
+    /// aren't run again while rendering. The variables already present
+    /// are kept as they are. Without [`RenderOptions::shell_commands`]
+    /// the variables are set to the commands like `$(git rev-parse HEAD)`.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::path::PathBuf;
+    /// # use string_template_plus::{Render, RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let mut options = RenderOptions {
+    ///         wd: PathBuf::from("."),
+    ///         shell_commands: true,
+    ///         trim_command_output: true,
+    ///         ..Default::default()
+    ///     };
+    ///     options.command_vars.insert("greet".into(), "echo hello".into());
+    ///     let options = options.with_command_vars()?;
+    ///     assert_eq!(options.variables["greet"], "hello");
+    ///     let templ = Template::parse_template("{greet} {greet}")?;
+    ///     assert_eq!(templ.render(&options)?, "hello hello");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_command_vars(&self) -> Result<Self, Error> {
+        let mut op = self.clone();
+        op.command_vars.clear();
+        for (name, cmd) in &self.command_vars {
+            if op.variables.contains_key(name) {
+                continue;
+            }
+            let val = if self.shell_commands {
+                self.emit(RenderEvent::CommandRun(cmd));
+                cmd_output_retry(cmd, self, None)?
+            } else {
+                format!("$({cmd})")
+            };
+            op.variables.insert(name.to_string(), val);
+        }
+        Ok(op)
+    }
+
+    /// Reads the variables from a JSON (with `json` feature) or TOML
+    /// (with `toml` feature) file, decided by the file extension.
+    /// Nested values are flattened with dotted keys, like `a.b` for
+    /// `{"a": {"b": 1}}` and `a.0` for the first element of an array.
+    ///
+    /// ```rust,no_run
+    /// # use std::error::Error;
+    /// # use string_template_plus::{Render, RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let options = RenderOptions {
+    ///         variables: RenderOptions::variables_from_file("vars.json")?,
+    ///         ..Default::default()
+    ///     };
+    ///     let templ = Template::parse_template("hello {user.name}")?;
+    ///     println!("{}", templ.render(&options)?);
+    /// # Ok(())
+    /// # }
+    #[cfg(any(feature = "json", feature = "toml"))]
+    pub fn variables_from_file<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> Result<HashMap<String, String>, Error> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let mut variables = HashMap::new();
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "json")]
+            Some("json") => {
+                flatten_json("", &serde_json::from_str(&contents)?, &mut variables);
+            }
+            #[cfg(feature = "toml")]
+            Some("toml") => {
+                let table: toml::Table = toml::from_str(&contents)?;
+                flatten_toml("", &toml::Value::Table(table), &mut variables);
+            }
+            _ => Err(errors::RenderTemplateError::InvalidFormat(
+                path.to_string_lossy().to_string(),
+                "Unsupported file type for variables".to_string(),
+            ))?,
+        }
+        Ok(variables)
+    }
+
+    /// Sends the event to the [`EventHandler`] if there is one
+    fn emit(&self, event: RenderEvent) {
+        if let Some(handler) = &self.on_event {
+            (handler.0)(event);
+        }
+    }
+
+    /// Makes a [`RenderIter<'a>`] that can generate incremented strings from the given [`Template`] and the [`RenderOptions`]. The Iterator will have `-N` appended where N is the number representing the number of instance.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use std::collections::HashMap;
+    /// # use string_template_plus::{Render, RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("hello {name}").unwrap();
+    ///     let mut vars: HashMap<String, String> = HashMap::new();
+    ///     vars.insert("name".into(), "world".into());
+    ///     let options = RenderOptions {
+    ///         variables: vars,
+    ///         ..Default::default()
+    ///     };
+    ///     let mut names = options.render_iter(&templ);
+    ///     assert_eq!("hello world-1", names.next().unwrap());
+    ///     assert_eq!("hello world-2", names.next().unwrap());
+    ///     assert_eq!("hello world-3", names.next().unwrap());
+    /// # Ok(())
+    /// # }
+    pub fn render_iter<'a>(&'a self, templ: &'a Template) -> RenderIter<'a> {
+        RenderIter {
+            template: templ,
+            options: self,
+            count: 0,
+        }
+    }
+
+    /// Makes a [`RenderRangeIter<'a, I>`] that renders the [`Template`]
+    /// for each of the values, with the given variable set to the value.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # use string_template_plus::{RenderOptions, Template};
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    ///     let templ = Template::parse_template("file-{i}.txt").unwrap();
+    ///     let options = RenderOptions::default();
+    ///     let names: Vec<String> = options
+    ///         .render_range(&templ, "i", 1..=3)
+    ///         .collect::<Result<_, _>>()?;
+    ///     assert_eq!(names, ["file-1.txt", "file-2.txt", "file-3.txt"]);
+    /// # Ok(())
+    /// # }
+    pub fn render_range<'a, I>(
+        &self,
+        templ: &'a Template,
+        variable: &str,
+        values: I,
+    ) -> RenderRangeIter<'a, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: ToString,
+    {
+        RenderRangeIter::new(templ, self, variable, values.into_iter())
+    }
+}
+
+/// Render option with [`Iterator`] support. You can use this to get
+/// incremented render results. It'll add `-N` to the render
+/// [`Template`] where `N` is the count (1,2,3...). It can be useful
+/// to make files with a given template.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use string_template_plus::{Render, RenderOptions, RenderIter, Template};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let templ = Template::parse_template("hello {name}").unwrap();
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("name".into(), "world".into());
+///     let options = RenderOptions {
+///         variables: vars,
+///         ..Default::default()
+///     };
+///     let mut names = RenderIter::new(&templ, &options);
+///     assert_eq!("hello world-1", names.next().unwrap());
+///     assert_eq!("hello world-2", names.next().unwrap());
+///     assert_eq!("hello world-3", names.next().unwrap());
+/// # Ok(())
+/// # }
+#[derive(Debug, Clone)]
+pub struct RenderIter<'a> {
+    template: &'a Template,
+    options: &'a RenderOptions,
+    count: usize,
+}
+
+impl<'a> RenderIter<'a> {
+    /// Creates a new [`RenderIter<'a>`] object
+    pub fn new(template: &'a Template, options: &'a RenderOptions) -> Self {
+        Self {
+            template,
+            options,
+            count: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for RenderIter<'a> {
+    type Item = String;
+    fn next(&mut self) -> Option<String> {
+        self.template.render(self.options).ok().map(|t| {
+            self.count += 1;
+            format!("{}-{}", t, self.count)
+        })
+    }
+}
+
+/// Renders the [`Template`] once for each of the values with the
+/// variable set to that value, made by [`RenderOptions::render_range`]
+#[derive(Debug, Clone)]
+pub struct RenderRangeIter<'a, I> {
+    template: &'a Template,
+    options: RenderOptions,
+    variable: String,
+    values: I,
+}
+
+impl<'a, I> RenderRangeIter<'a, I> {
+    /// Creates a new [`RenderRangeIter<'a, I>`] object
+    pub fn new(template: &'a Template, options: &RenderOptions, variable: &str, values: I) -> Self {
+        Self {
+            template,
+            options: options.clone(),
+            variable: variable.to_string(),
+            values,
+        }
+    }
+}
+
+impl<'a, I> Iterator for RenderRangeIter<'a, I>
+where
+    I: Iterator,
+    I::Item: ToString,
+{
+    type Item = Result<String, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        self.options
+            .variables
+            .insert(self.variable.clone(), value.to_string());
+        Some(self.template.render(&self.options))
+    }
+}
+
+impl TemplatePart {
+    /// Byte position up to which the template only has complete
+    /// parts, the rest might need more text to be parsed
+    fn stream_boundary(templ: &str) -> usize {
+        let mut i = 0;
+        while i < templ.len() {
+            let rest = &templ[i..];
+            if rest.starts_with(ESCAPE_CHAR) {
+                match rest[ESCAPE_CHAR.len_utf8()..].chars().next() {
+                    Some(c) => i += ESCAPE_CHAR.len_utf8() + c.len_utf8(),
+                    None => return i,
+                }
+                continue;
+            }
+            let end = if rest.starts_with("$(") || rest.starts_with("=(") {
+                Self::find_end(')', templ, i + 2)
+            } else if rest.starts_with('{') {
+                Self::find_end('}', templ, i + 1)
+            } else if rest.starts_with('"') {
+                Self::find_end('"', templ, i + 1)
+            } else if rest == "$" || rest == "=" {
+                // might be the start of a command or lisp expression
+                return i;
+            } else {
+                i += rest.chars().next().map_or(1, |c| c.len_utf8());
+                continue;
+            };
+            match end {
+                Ok(e) => i = e + 1,
+                Err(_) => return i,
+            }
+        }
+        templ.len()
+    }
+
+    /// Renders the placeholders like `{from}` in the transformer
+    /// arguments if [`RenderOptions::render_transformer_args`] is set,
+    /// the rest of the text is kept as it is
+    fn render_transformer_args(
+        transformations: &str,
+        op: &RenderOptions,
+        vars: &dyn Variables,
+        depth: usize,
+    ) -> Result<String, Error> {
+        if !op.render_transformer_args || !transformations.contains('{') {
+            return Ok(transformations.to_string());
+        }
+        let mut rendered = String::new();
+        let mut last = 0;
+        let mut i = 0;
+        while i < transformations.len() {
+            if transformations[i..].starts_with(ESCAPE_CHAR) {
+                // the escapes are removed later with the arguments
+                i += ESCAPE_CHAR.len_utf8()
+                    + transformations[(i + ESCAPE_CHAR.len_utf8())..]
+                        .chars()
+                        .next()
+                        .map_or(0, |c| c.len_utf8());
+                continue;
+            }
+            if transformations[i..].starts_with('{') {
+                let end = Self::find_end('}', transformations, i + 1)?;
+                rendered.push_str(&transformations[last..i]);
+                let part = Self::maybe_any(&transformations[(i + 1)..end]);
+                // the value is a single literal argument
+                for c in part.render_nested(op, vars, depth + 1)?.chars() {
+                    if ESCAPED_CHARS.contains(c) {
+                        rendered.push(ESCAPE_CHAR);
+                    }
+                    rendered.push(c);
+                }
+                last = end + 1;
+                i = end;
+            }
+            i += transformations[i..]
+                .chars()
+                .next()
+                .map_or(1, |c| c.len_utf8());
+        }
+        rendered.push_str(&transformations[last.min(transformations.len())..]);
+        Ok(rendered)
+    }
+
+    /// Render the part that is nested `depth` levels deep inside
+    /// [`TemplatePart::Cmd`] and [`TemplatePart::Any`] parts
+    fn render_nested(
+        &self,
+        op: &RenderOptions,
+        vars: &dyn Variables,
+        depth: usize,
+    ) -> Result<String, Error> {
+        let max_depth = op.max_depth.unwrap_or(MAX_RENDER_DEPTH);
+        if depth > max_depth {
+            return Err(errors::RenderTemplateError::MaxDepthExceeded(max_depth).into());
+        }
+        match self {
+            TemplatePart::Lit(l) => Ok(l.to_string()),
+            TemplatePart::SkipEmpty => Ok(String::new()),
+            TemplatePart::Var(v, f) => match vars.get(v) {
+                Some(s) => {
+                    op.emit(RenderEvent::VariableResolved(v, &s));
+                    let f = transformers::expand_aliases(f, &op.transformer_aliases)?;
+                    let f = Self::render_transformer_args(&f, op, vars, depth)?;
+                    Ok(transformers::apply_tranformers_with_source(
+                        &s, &f, op, vars,
+                    )?)
+                }
+                None => {
+                    op.emit(RenderEvent::VariableMissing(v));
+                    Err(errors::RenderTemplateError::VariableNotFound(v.to_string()).into())
+                }
+            },
+            TemplatePart::Time(t) => {
+                Self::check_time_format(t)?;
+                Ok(op.now.unwrap_or_else(Local::now).format(t).to_string())
+            }
+            TemplatePart::Lisp(e, f, _) => {
+                let lisp_vars = Self::lisp_variables(e, vars, op);
+                let res = match op.lisp_timeout {
+                    Some(t) => lisp::evaluate_timeout(&lisp_vars, e, t)?,
+                    None => match lisp::evaluate(&lisp_vars, e)? {
+                        Value::String(s) => s,
+                        v => v.to_string(),
+                    },
+                };
+                op.emit(RenderEvent::LispEvaluated(e, &res));
+                let f = transformers::expand_aliases(f, &op.transformer_aliases)?;
+                let f = Self::render_transformer_args(&f, op, vars, depth)?;
+                Ok(transformers::apply_tranformers_with_source(
+                    &res, &f, op, vars,
+                )?)
+            }
+            TemplatePart::Cmd(c) => {
+                let render_parts = |parts: &[TemplatePart]| {
+                    parts
+                        .iter()
+                        .map(|p| p.render_nested(op, vars, depth + 1))
+                        .collect::<Result<String, Error>>()
+                };
+                let (cmd, stdin) = match c.as_slice() {
+                    [rest @ .., TemplatePart::Lit(l), input] if l.ends_with(CMD_STDIN_MARKER) => {
+                        let cmd = render_parts(rest)? + &l[..(l.len() - CMD_STDIN_MARKER.len())];
+                        (cmd, Some(input.render_nested(op, vars, depth + 1)?))
+                    }
+                    _ => (render_parts(c)?, None),
+                };
+                if op.shell_commands {
+                    op.emit(RenderEvent::CommandRun(&cmd));
+                    cmd_output_retry(&cmd, op, stdin.as_deref())
+                } else if let Some(input) = stdin {
+                    Ok(format!("$({cmd}{CMD_STDIN_MARKER}{input})"))
+                } else {
+                    Ok(format!("$({cmd})"))
+                }
+            }
+            TemplatePart::Any(a) => {
+                for (i, p) in a.iter().enumerate() {
+                    if *p == TemplatePart::SkipEmpty {
+                        continue;
+                    }
+                    if let TemplatePart::Var(v, _) = p {
+                        match vars.get(v) {
+                            Some(s)
+                                if s.is_empty()
+                                    && (op.empty_is_missing
+                                        || a.get(i + 1) == Some(&TemplatePart::SkipEmpty)) =>
+                            {
+                                op.emit(RenderEvent::VariableResolved(v, &s));
+                                continue;
+                            }
+                            _ => (),
+                        }
+                    }
+                    match p.render_nested(op, vars, depth + 1) {
+                        Ok(s) => return Ok(s),
+                        // too deep nesting is not a missing value, so no fallback
+                        Err(e) => {
+                            if let Some(errors::RenderTemplateError::MaxDepthExceeded(_)) =
+                                e.downcast_ref()
+                            {
+                                return Err(e);
+                            }
+                        }
+                    }
+                }
+                Err(errors::RenderTemplateError::AllVariablesNotFound(
+                    a.iter()
+                        .filter(|p| **p != TemplatePart::SkipEmpty)
+                        .map(|p| p.to_string())
+                        .collect(),
+                )
+                .into())
+            }
+        }
+    }
+}
+
+impl Render for TemplatePart {
+    fn render(&self, op: &RenderOptions) -> Result<String, Error> {
+        self.render_nested(op, &op.variables, 0)
+    }
+
+    /// Visualize what has been parsed so it's easier to debug
+    fn print_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.visualize(w, colored::control::SHOULD_COLORIZE.should_colorize())
+    }
+}
+
+impl Render for Vec<TemplatePart> {
+    fn render(&self, op: &RenderOptions) -> Result<String, Error> {
+        self.iter()
+            .map(|p| p.render(op))
+            .collect::<Result<Vec<String>, Error>>()
+            .map(|v| v.join(""))
+    }
+
+    fn print_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.iter().try_for_each(|p| p.print_to(w))
+    }
+}
+
+impl Render for Template {
+    fn render(&self, op: &RenderOptions) -> Result<String, Error> {
+        let _once = transformers::OnceScope::new();
+        if op.command_vars.is_empty() {
+            self.parts.render(op)
+        } else {
+            self.parts.render(&op.with_command_vars()?)
+        }
+    }
+
+    fn print_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.parts.print_to(w)
+    }
+}
+
+/// Parses the string as a [`Template`] and renders it in one step,
+/// the string is parsed again on every call, so parse it once with
+/// [`Template::parse_template`] for rendering it repeatedly.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use string_template_plus::{Render, RenderOptions};
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("name".into(), "world".into());
+///     let options = RenderOptions {
+///         variables: vars,
+///         ..Default::default()
+///     };
+///     assert_eq!("hello {name:case(up)}".render(&options)?, "hello WORLD");
+///     assert!("hello {name".render(&options).is_err());
+/// # Ok(())
+/// # }
+impl Render for str {
+    fn render(&self, op: &RenderOptions) -> Result<String, Error> {
+        Template::parse_template(self)?.render(op)
+    }
+
+    fn print_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        Template::parse_template(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?
+            .print_to(w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lit() {
+        let templ = Template::parse_template("hello name").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "hello name");
+    }
+
+    #[test]
+    fn test_vars() {
+        let templ = Template::parse_template("hello {name}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "hello world");
+    }
+
+    #[test]
+    fn test_vars_format() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("length".into(), "120.1234".into());
+        vars.insert("name".into(), "joHN".into());
+        vars.insert("job".into(), "assistant manager of company".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let cases = [
+            ("L={length}", "L=120.1234"),
+            ("L={length:calc(+100)}", "L=220.1234"),
+            ("L={length:count(.):calc(+1)}", "L=2"),
+            ("L={length:f(.2)} ({length:f(3)})", "L=120.12 (120.123)"),
+            ("hi {name:case(up)}", "hi JOHN"),
+            (
+                "hi {name:case(proper)}, {job:case(title)}",
+                "hi John, Assistant Manager of Company",
+            ),
+            ("hi {name:case(down)}", "hi john"),
+        ];
+
+        for (t, r) in cases {
+            let templ = Template::parse_template(t).unwrap();
+            let rendered = templ.render(&options).unwrap();
+            assert_eq!(rendered, r);
+        }
+    }
+
+    #[test]
+    fn test_printf() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("length".into(), "120.1234".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let cases = [
+            ("L={length:printf(%08.2f)}", "L=00120.12"),
+            ("L={length:printf(%.1f m)}", "L=120.1 m"),
+        ];
+
+        for (t, r) in cases {
+            let templ = Template::parse_template(t).unwrap();
+            let rendered = templ.render(&options).unwrap();
+            assert_eq!(rendered, r);
+        }
+    }
+
+    #[test]
+    fn test_oxford() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("names".into(), "tom,dick,harry".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let cases = [
+            ("{names:oxford(,)}", "tom, dick, and harry"),
+            ("{names:oxford(,,or)}", "tom, dick, or harry"),
+        ];
+
+        for (t, r) in cases {
+            let templ = Template::parse_template(t).unwrap();
+            let rendered = templ.render(&options).unwrap();
+            assert_eq!(rendered, r);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_novars() {
+        let templ = Template::parse_template("hello {name}").unwrap();
+        let vars: HashMap<String, String> = HashMap::new();
+        templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_novars_opt() {
+        let templ = Template::parse_template("hello {name?}").unwrap();
+        let vars: HashMap<String, String> = HashMap::new();
+        let rendered = templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "hello ");
+    }
+
+    #[test]
+    fn test_optional() {
+        let templ = Template::parse_template("hello {age?name}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "hello world");
+    }
+
+    #[test]
+    fn test_special_chars() {
+        let templ = Template::parse_template("$hello {}? \\{\\}%").unwrap();
+        let rendered = templ.render(&RenderOptions::default()).unwrap();
+        assert_eq!(rendered, "$hello ? {}%");
+    }
+
+    #[test]
+    fn test_special_chars2() {
+        let templ = Template::parse_template("$hello {}? \"{\"\"}\"%").unwrap();
+        let rendered = templ.render(&RenderOptions::default()).unwrap();
+        assert_eq!(rendered, "$hello ? {}%");
+    }
+
+    #[test]
+    fn test_optional_lit() {
+        let templ = Template::parse_template("hello {age?\"20\"}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "hello 20");
+    }
+
+    #[test]
+    fn test_command() {
+        let templ = Template::parse_template("hello $(echo {name})").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                wd: PathBuf::from("."),
+                variables: vars,
+                shell_commands: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "hello world\n");
+    }
+
+    #[test]
+    fn test_command_quote() {
+        let templ = Template::parse_template("hello $(printf \\\"%s %d\\\" {name} {age})").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        vars.insert("age".into(), "1".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                wd: PathBuf::from("."),
+                variables: vars,
+                shell_commands: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "hello world 1");
+    }
+
+    #[test]
+    fn test_command_failed() {
+        let templ = Template::parse_template("hello $(echo partial; exit 3)").unwrap();
+        let mut options = RenderOptions {
+            wd: PathBuf::from("."),
+            shell_commands: true,
+            ..Default::default()
+        };
+        assert_eq!(templ.render(&options).unwrap(), "hello partial\n");
+        options.fail_on_command_error = true;
+        let err = templ.render(&options).unwrap_err();
+        match err.downcast_ref::<errors::RenderTemplateError>() {
+            Some(errors::RenderTemplateError::CommandFailed(cmd, status, out)) => {
+                assert_eq!(cmd, "echo partial; exit 3");
+                assert_eq!(status, "Exited(3)");
+                assert_eq!(out, "partial\n");
+            }
+            _ => panic!("Expected CommandFailed error, got: {err}"),
+        }
+    }
+
+    #[test]
+    fn test_time() {
+        let templ = Template::parse_template("hello {name} at {%Y-%m-%d}").unwrap();
+        let timefmt = Local::now().format("%Y-%m-%d");
+        let output = format!("hello world at {}", timefmt);
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                wd: PathBuf::from("."),
+                variables: vars,
+                shell_commands: false,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, output);
+    }
+
+    #[test]
+    fn test_var_or_time() {
+        let templ = Template::parse_template("hello {name} at {age?%Y-%m-%d}").unwrap();
+        let timefmt = Local::now().format("%Y-%m-%d");
+        let output = format!("hello world at {}", timefmt);
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                wd: PathBuf::from("."),
+                variables: vars,
+                shell_commands: false,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, output);
+    }
+
+    #[test]
+    fn test_render_iter() {
+        let templ = Template::parse_template("hello {name}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let mut names = options.render_iter(&templ);
+        assert_eq!("hello world-1", names.next().unwrap());
+        assert_eq!("hello world-2", names.next().unwrap());
+        assert_eq!("hello world-3", names.next().unwrap());
+    }
+
+    #[test]
+    fn test_word_count() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("text".into(), "a  b ".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let cases = [("{text:count( ):calc(+1)}", "4"), ("{text:wc(words)}", "2")];
+
+        for (t, r) in cases {
+            let templ = Template::parse_template(t).unwrap();
+            let rendered = templ.render(&options).unwrap();
+            assert_eq!(rendered, r);
+        }
+    }
+
+    #[test]
+    fn test_calc_adjacent_operators() {
+        let templ = Template::parse_template("{x:calc(++1)}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("x".into(), "1".into());
+        let err = templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "calc expression ++1 is invalid: adjacent operators ++ before 1"
+        );
+    }
+
+    #[test]
+    fn test_render_all() {
+        let templ = Template::parse_template("{greeting} {name}{punct?\".\"}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("greeting".into(), "hello".into());
+        vars.insert("name".into(), "nobody".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let rows: Vec<HashMap<String, String>> = vec![
+            HashMap::from([("name".into(), "world".into())]),
+            HashMap::from([
+                ("name".into(), "there".into()),
+                ("punct".into(), "!".into()),
+            ]),
+            HashMap::from([("greeting".into(), "bye".into())]),
+        ];
+        let rendered: Vec<String> = templ
+            .render_all(&options, &rows)
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rendered, ["hello world.", "hello there!", "bye nobody."]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_render_all_parallel() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<Template>();
+        assert_sync::<RenderOptions>();
+
+        let templ = Template::parse_template("{name} =(* 2 (st+num 'n)) {n:calc(+1)}").unwrap();
+        let options = RenderOptions {
+            variables: HashMap::from([("name".into(), "row".into())]),
+            ..Default::default()
+        };
+        let rows: Vec<HashMap<String, String>> = (0..100)
+            .map(|i| HashMap::from([("n".into(), i.to_string())]))
+            .collect();
+        let seq: Vec<String> = templ
+            .render_all(&options, &rows)
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+        let par: Vec<String> = templ
+            .render_all_parallel(&options, &rows)
+            .into_iter()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(seq, par);
+    }
+
+    #[test]
+    fn test_transformer_specs() {
+        for spec in transformers::transformer_specs() {
+            let res = transformers::apply_tranformers("1", &format!("{}()", spec.name));
+            assert!(
+                !matches!(res, Err(errors::TransformerError::UnknownTranformer(..))),
+                "{} has a spec but isn't dispatched",
+                spec.name
+            );
+        }
+        assert!(transformers::transformer_spec("nope").is_none());
+    }
+
+    #[test]
+    fn test_check_transformers() {
+        let templ =
+            Template::parse_template("hi {name:case(up)} $(echo {age?x:f(2):nope()})").unwrap();
+        match templ.check_transformers() {
+            Err(errors::TransformerError::UnknownTranformer(name, var)) => {
+                assert_eq!(name, "nope");
+                assert_eq!(var, "x");
+            }
+            r => panic!("Expected UnknownTranformer, got {r:?}"),
+        }
+    }
+
+    #[test]
+    fn test_lisp_transformers() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        vars.insert("x".into(), "1.5".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let cases = [
+            ("=(st+var 'name)", "world"),
+            ("{=(st+var 'name):case(up)}", "WORLD"),
+            ("{=(st+var 'name):q()}", "\"world\""),
+            ("{=(* 2 (st+num 'x)):calc(+1):f(2)}", "4.00"),
+            ("{=(st+has 'name):case(down)}", "t"),
+        ];
+
+        for (t, r) in cases {
+            let templ = Template::parse_template(t).unwrap();
+            let rendered = templ.render(&options).unwrap();
+            assert_eq!(rendered, r);
+        }
+    }
+
+    #[test]
+    fn test_render_events() {
+        let templ =
+            Template::parse_template("{nickname?name} =(+ 1 (st+num 'x)) $(echo {name})").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        vars.insert("x".into(), "1".into());
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let ev = events.clone();
+        let options = RenderOptions {
+            wd: PathBuf::from("."),
+            variables: vars,
+            shell_commands: true,
+            on_event: Some(EventHandler::new(move |e| {
+                ev.lock().unwrap().push(format!("{e:?}"))
+            })),
+            ..Default::default()
+        };
+        templ.render(&options).unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "VariableMissing(\"nickname\")",
+                "VariableResolved(\"name\", \"world\")",
+                "LispEvaluated(\"(+ 1 (st+num 'x))\", \"2\")",
+                "VariableResolved(\"name\", \"world\")",
+                "CommandRun(\"echo world\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crlf_lines() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("out".into(), "first\r\nsecond\r\nthird\r\n".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let cases = [
+            ("[{out:take(\n,2)}]", "[second]"),
+            ("{out:count(\n)}", "3"),
+            ("{out:wc(lines)}", "3"),
+        ];
+
+        for (t, r) in cases {
+            let templ = Template::parse_template(t).unwrap();
+            let rendered = templ.render(&options).unwrap();
+            assert_eq!(rendered, r);
+        }
+    }
+
+    #[test]
+    fn test_parse_one() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("length".into(), "120.1234".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let part = TemplatePart::parse_one("{length:calc(+1):f(.1)}").unwrap();
+        assert!(matches!(part, TemplatePart::Var(..)));
+        assert_eq!(part.render(&options).unwrap(), "121.1");
+        assert!(TemplatePart::parse_one("").is_err());
+        assert!(TemplatePart::parse_one("{length}m").is_err());
+    }
+
+    #[test]
+    fn test_escape_lisp() {
+        let templ = Template::parse_template("total\\=(1 + 1) =(+ 1 1)").unwrap();
+        let rendered = templ.render(&RenderOptions::default()).unwrap();
+        assert_eq!(rendered, "total=(1 + 1) 2");
+    }
+
+    #[test]
+    fn test_literal_text() {
+        let templ = Template::parse_template("hello {name}, welcome").unwrap();
+        assert_eq!(templ.lit(), None);
+        assert_eq!(templ.literal_text(), "hello , welcome");
+        let templ = Template::parse_template("hi {name?\"user\"} $(echo {age}) =(+ 1 2)!").unwrap();
+        assert_eq!(templ.literal_text(), "hi user echo  !");
+    }
+
+    #[test]
+    fn test_optional_transformer_error() {
+        let templ = Template::parse_template("{price:f(2)?\"N/A\"} {cost:f(2)?\"N/A\"}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("price".into(), "free".into());
+        vars.insert("cost".into(), "1.234".into());
+        let rendered = templ
+            .render(&RenderOptions {
+                variables: vars,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rendered, "N/A 1.23");
+    }
+
+    #[test]
+    fn test_render_lines() {
+        let templ = Template::parse_template("hello {name}\nhi {age}\nbye {name}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let lines = templ.render_lines(&options);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].as_ref().unwrap(), "hello world");
+        assert!(lines[1].is_err());
+        assert_eq!(lines[2].as_ref().unwrap(), "bye world");
+    }
+
+    #[test]
+    fn test_print_to() {
+        let templ = Template::parse_template("hello {name:case(up)} $(echo hi)").unwrap();
+        let mut buf: Vec<u8> = Vec::new();
+        templ.print_to(&mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(out.starts_with("hello "));
+        assert!(out.contains("name"));
+        assert!(out.contains("case(up)"));
+        assert!(out.contains("echo hi"));
+        assert!(out.contains("$("));
+
+        // only `render` is needed to implement the trait
+        struct Greeting;
+        impl Render for Greeting {
+            fn render(&self, _: &RenderOptions) -> Result<String, Error> {
+                Ok("hello".into())
+            }
+        }
+        let mut buf: Vec<u8> = Vec::new();
+        Greeting.print_to(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_print_plain() {
+        let templ =
+            Template::parse_template("hello {name:case(up)?\"you\"} $(echo {age}) =(+ 1 2) {%Y}")
+                .unwrap();
+        let mut buf: Vec<u8> = Vec::new();
+        templ.print_plain_to(&mut buf).unwrap();
+        let out = String::from_utf8(buf).unwrap();
+        assert!(!out.contains('\x1B'));
+        assert_eq!(out, "hello name:case(up)?you $(echo age) (+ 1 2) %Y");
+    }
+
+    #[test]
+    fn test_lisp_transformer() {
+        let templ = Template::parse_template("{x:lisp((* (st+num 'n) (st+num 'self)))}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("x".into(), "21".into());
+        vars.insert("n".into(), "2".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        assert_eq!(templ.render(&options).unwrap(), "42");
+    }
+
+    #[test]
+    fn test_nested_cmd_any() {
+        // the parser doesn't make commands inside the optional parts
+        let inner = TemplatePart::parse_cmd("echo {name}").unwrap();
+        let any = TemplatePart::any(vec![TemplatePart::var("nick"), inner]);
+        let part = TemplatePart::cmd(vec![TemplatePart::lit("echo "), any]);
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let mut options = RenderOptions {
+            wd: PathBuf::from("."),
+            variables: vars,
+            shell_commands: true,
+            ..Default::default()
+        };
+        assert_eq!(part.render(&options).unwrap(), "world\n");
+        options.variables.insert("nick".into(), "hello".into());
+        assert_eq!(part.render(&options).unwrap(), "hello\n");
+        options.variables.remove("nick");
+        options.max_depth = Some(2);
+        let err = part.render(&options).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(errors::RenderTemplateError::MaxDepthExceeded(2))
+        ));
+        options.max_depth = Some(3);
+        assert_eq!(part.render(&options).unwrap(), "world\n");
+    }
+
+    #[test]
+    fn test_part_spans() {
+        let src = "hi \\{x} {name:case(up)?\"you\"} $(echo {age}) =(+ 1 2) \"lit\" {%Y}";
+        let templ = Template::parse_template(src).unwrap();
+        let spans = templ.part_spans();
+        assert_eq!(spans.len(), templ.parts().len());
+        let joined: String = spans.iter().map(|(r, _)| &src[r.clone()]).collect();
+        assert_eq!(joined, src);
+        for (r, p) in spans {
+            assert_eq!(&TemplatePart::parse_one(&src[r]).unwrap(), p);
+        }
+    }
+
+    #[test]
+    fn test_cmd_stdin() {
+        let templ =
+            Template::parse_template("$(cat|stdin:{json}) $(tr a-z A-Z |stdin:\"hi\")").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("json".into(), "{\"name\": \"x\"}".into());
+        let mut options = RenderOptions {
+            wd: PathBuf::from("."),
+            variables: vars,
+            shell_commands: true,
+            ..Default::default()
+        };
+        assert_eq!(templ.render(&options).unwrap(), "{\"name\": \"x\"} HI");
+        options.shell_commands = false;
+        assert_eq!(
+            templ.render(&options).unwrap(),
+            "$(cat|stdin:{\"name\": \"x\"}) $(tr a-z A-Z |stdin:hi)"
+        );
+    }
+
+    #[test]
+    fn test_command_retries() {
+        let flag = std::env::temp_dir().join(format!("stp-retry-{}", std::process::id()));
+        let _ = std::fs::remove_file(&flag);
+        let templ = Template::parse_template(
+            "$(if [ -f {flag} ]; then echo ok; else touch {flag}; exit 1; fi)",
+        )
+        .unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("flag".into(), flag.to_string_lossy().to_string());
+        let mut options = RenderOptions {
+            wd: PathBuf::from("."),
+            variables: vars,
+            shell_commands: true,
+            fail_on_command_error: true,
+            ..Default::default()
+        };
+        assert!(templ.render(&options).is_err());
+        std::fs::remove_file(&flag).unwrap();
+        options.command_retries = 1;
+        options.command_retry_delay = Duration::from_millis(10);
+        assert_eq!(templ.render(&options).unwrap(), "ok\n");
+        std::fs::remove_file(&flag).unwrap();
+    }
+
+    #[test]
+    fn test_render_cow() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("hello world").unwrap();
+        assert!(matches!(
+            templ.render_cow(&options).unwrap(),
+            Cow::Borrowed(_)
+        ));
+        let templ = Template::parse_template("").unwrap();
+        assert!(matches!(
+            templ.render_cow(&options).unwrap(),
+            Cow::Borrowed("")
+        ));
+        let templ = Template::parse_template("\\{hello\\} \"{name}\"").unwrap();
+        assert!(templ.parts().len() > 1);
+        assert!(matches!(
+            templ.render_cow(&options).unwrap(),
+            Cow::Borrowed("{hello} {name}")
+        ));
+        assert!(matches!(
+            templ.minify().render_cow(&options).unwrap(),
+            Cow::Borrowed("{hello} {name}")
+        ));
+        let templ = Template::parse_template("hello {name}").unwrap();
+        let rendered = templ.render_cow(&options).unwrap();
+        assert!(matches!(rendered, Cow::Owned(_)));
+        assert_eq!(rendered, "hello world");
+    }
+
+    #[test]
+    fn test_render_bytes() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "wörld".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("hello {name}").unwrap();
+        assert_eq!(
+            templ.render_bytes(&options).unwrap(),
+            templ.render(&options).unwrap().into_bytes()
+        );
+    }
+
+    #[test]
+    fn test_validate_commands() {
+        let allowed = ["echo", "date", "tr"];
+        let templ = Template::parse_template(
+            "$(echo {name} | tr a-z A-Z) $(FOO=1 date && echo \\\"a|b\\\")",
+        )
+        .unwrap();
+        assert!(templ.validate_commands(&allowed).is_ok());
+        let templ = Template::parse_template("hi $(echo {name}; rm -rf {dir})").unwrap();
+        let err = templ.validate_commands(&allowed).unwrap_err();
+        assert!(matches!(
+            err,
+            errors::RenderTemplateError::CommandNotAllowed(_, ref p) if p == "rm"
+        ));
+        // programs from the variables or other commands are unknown
+        let templ = Template::parse_template("$({prog} x)").unwrap();
+        assert!(templ.validate_commands(&allowed).is_err());
+        let templ = Template::parse_template("$(echo $(date | sh))").unwrap();
+        assert!(templ.validate_commands(&allowed).is_err());
+    }
+
+    #[test]
+    fn test_escapes() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("x".into(), "f(a,b)".into());
+        vars.insert("n".into(), "123".into());
+        vars.insert("a?b".into(), "q".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let cases = [
+            // transformer arguments
+            (r"{x:repl(\,,;)}", "f(a;b)"),
+            (r"{x:repl(\),\}):repl(\(,\{)}", "f{a,b}"),
+            (r"{x:repl(\,,\:):case(up)}", "F(A:B)"),
+            (r"{n:when(re:^\d+$,number,text)}", "number"),
+            // alternatives
+            (r#"{y?"who\?"}"#, "who?"),
+            (r#"{y?"\"quoted\""}"#, "\"quoted\""),
+            (r"{a\?b?y}", "q"),
+            // outside the braces
+            (r"\{x} \$(echo)", "{x} $(echo)"),
+        ];
+        for (t, r) in cases {
+            let templ = Template::parse_template(t).unwrap();
+            assert_eq!(templ.render(&options).unwrap(), r, "{t}");
+        }
+    }
+
+    #[test]
+    fn test_once() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("header".into(), "# Items\n".into());
+        vars.insert("item".into(), "- {header:once()}{name}\n".into());
+        vars.insert("name".into(), "apple".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        // nested renders share the same keys
+        let templ =
+            Template::parse_template("{header:once()}{item:render()}{item:render()}").unwrap();
+        assert_eq!(
+            templ.render(&options).unwrap(),
+            "# Items\n- apple\n- apple\n"
+        );
+        let mut out: Vec<u8> = Vec::new();
+        Template::render_stream(templ.original().as_bytes(), &options, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# Items\n- apple\n- apple\n"
+        );
+        let templ = Template::parse_template("{header:once()}a{header:once()}").unwrap();
+        let (rendered, err) = templ.render_partial(&options);
+        assert!(err.is_none());
+        assert_eq!(rendered, "# Items\na");
+        assert_eq!(templ.render_streams(&options).unwrap()[""], "# Items\na");
+    }
+
+    #[test]
+    fn test_builtin_transformers() {
+        let names: Vec<&str> = transformers::transformer_specs()
+            .iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(transformers::BUILTIN_TRANSFORMERS, names.as_slice());
+        for name in transformers::BUILTIN_TRANSFORMERS {
+            assert!(transformers::is_builtin_transformer(name), "{name}");
+            let spec = transformers::transformer_spec(name).unwrap();
+            let args = vec!["1"; spec.min_args].join(",");
+            let res = transformers::apply_tranformers("1", &format!("{name}({args})"));
+            assert!(
+                !matches!(res, Err(errors::TransformerError::UnknownTranformer(..))),
+                "{name} isn't dispatched"
+            );
+        }
+        for name in ["", "nope", "CASE", "case()", "@money", "@case"] {
+            assert!(!transformers::is_builtin_transformer(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_now() {
+        use chrono::TimeZone;
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("posted".into(), "2024-03-07".into());
+        let options = RenderOptions {
+            variables: vars,
+            now: Local.with_ymd_and_hms(2024, 3, 10, 12, 0, 0).single(),
+            ..Default::default()
+        };
+        let templ = Template::parse_template("{%F}: posted {posted:ago(%F)}").unwrap();
+        assert_eq!(
+            templ.render(&options).unwrap(),
+            "2024-03-10: posted 3 days ago"
+        );
+    }
+
+    #[test]
+    fn test_render_with() {
+        struct Point(i32, i32);
+        impl Variables for Point {
+            fn get(&self, name: &str) -> Option<Cow<'_, str>> {
+                match name {
+                    "x" => Some(Cow::Owned(self.0.to_string())),
+                    "y" => Some(Cow::Owned(self.1.to_string())),
+                    "label" => Some(Cow::Borrowed("")),
+                    "visible" => Some(Cow::Borrowed("true")),
+                    _ => None,
+                }
+            }
+        }
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("x".into(), "0".into());
+        vars.insert("name".into(), "P".into());
+        vars.insert("from".into(), "1".into());
+        vars.insert("snippet".into(), "{x}/{y}".into());
+        let options = RenderOptions {
+            variables: vars,
+            render_transformer_args: true,
+            ..Default::default()
+        };
+        let templ =
+            Template::parse_template("{label??name}({x},{y:repl({from},one)}) {z?}").unwrap();
+        assert_eq!(
+            templ.render_with(&Point(1, -1), &options).unwrap(),
+            "P(1,-one) "
+        );
+        // the struct values are not copied into the options
+        assert!(templ.render(&options).is_err());
+        assert_eq!(
+            templ.parts()[0]
+                .render_with(&Point(1, -1), &options)
+                .unwrap(),
+            "P"
+        );
+        assert_eq!(options.variables.len(), 4);
+        // same as rendering with the values in the options
+        let templ = Template::parse_template(
+            "=(* 10 (st+num 'x)) {name:showif(visible)} {snippet:render()} {x:lisp((* (st+num 'y 0) (st+num 'self)))}",
+        )
+        .unwrap();
+        let rendered = templ.render_with(&Point(1, -1), &options).unwrap();
+        assert_eq!(rendered, "10 P 1/-1 -1");
+        let mut merged = options.clone();
+        for (k, v) in [("x", "1"), ("y", "-1"), ("label", ""), ("visible", "true")] {
+            merged.variables.insert(k.into(), v.into());
+        }
+        assert_eq!(templ.render(&merged).unwrap(), rendered);
+    }
+
+    #[test]
+    fn test_render_stream() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "wörld".into());
+        vars.insert("age".into(), "42".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ: String = (0..5000)
+            .map(|i| {
+                format!(
+                    "{i}: hello {{name:case(up)}} \\{{x}} \"{{q}}\" {{nick?name}} =(+ 1 {i}) {{age:calc(+{i})}}\n"
+                )
+            })
+            .collect();
+        let mut out: Vec<u8> = Vec::new();
+        Template::render_stream(templ.as_bytes(), &options, &mut out).unwrap();
+        let expected = Template::parse_template(&templ)
+            .unwrap()
+            .render(&options)
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[cfg(feature = "diff")]
+    #[test]
+    fn test_render_diff() {
+        let templ = Template::parse_template("hello {name}\nsame line\n").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        assert_eq!(
+            templ.render_diff(&options, "hello world\nsame line\n"),
+            None
+        );
+        let diff = templ
+            .render_diff(&options, "hello there\nsame line\n")
+            .unwrap();
+        assert_eq!(
+            diff,
+            "--- expected\n+++ rendered\n@@ -1,2 +1,2 @@\n-hello there\n+hello world\n same line\n"
+        );
+        let templ = Template::parse_template("hello {nickname}").unwrap();
+        assert!(templ
+            .render_diff(&options, "hello world")
+            .unwrap()
+            .starts_with("Render failed"));
+    }
+
+    #[test]
+    fn test_when_transformer() {
+        let templ = Template::parse_template("{status:when(re:^5,error,ok):case(up)}").unwrap();
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("status".into(), "503".into());
+        let mut options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        assert_eq!(templ.render(&options).unwrap(), "ERROR");
+        options.variables.insert("status".into(), "200".into());
+        assert_eq!(templ.render(&options).unwrap(), "OK");
     }
 
-    fn print(&self) {
-        self.iter().for_each(|p| p.print());
+    #[test]
+    fn test_command_output_lossy() {
+        let templ = Template::parse_template("$(printf 'a\\\\377b')").unwrap();
+        let mut options = RenderOptions {
+            wd: PathBuf::from("."),
+            shell_commands: true,
+            ..Default::default()
+        };
+        assert!(templ.render(&options).is_err());
+        options.command_output_lossy = true;
+        assert_eq!(templ.render(&options).unwrap(), "a\u{FFFD}b");
     }
-}
 
-impl Render for Template {
-    fn render(&self, op: &RenderOptions) -> Result<String, Error> {
-        self.parts.render(op)
+    #[test]
+    fn test_trim_command_output() {
+        let templ = Template::parse_template("hello $(echo world)!").unwrap();
+        let mut options = RenderOptions {
+            wd: PathBuf::from("."),
+            shell_commands: true,
+            ..Default::default()
+        };
+        assert_eq!(templ.render(&options).unwrap(), "hello world\n!");
+        options.trim_command_output = true;
+        assert_eq!(templ.render(&options).unwrap(), "hello world!");
+        let templ = Template::parse_template("$(printf 'a\\\\n\\\\n')").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "a\n");
     }
 
-    fn print(&self) {
-        self.parts.print();
+    #[test]
+    fn test_render_or() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("{greeting}, {name}").unwrap();
+        let fallback = Template::parse_template("hello {name}").unwrap();
+        assert_eq!(templ.render_or(&options, &fallback).unwrap(), "hello world");
+        assert_eq!(fallback.render_or(&options, &templ).unwrap(), "hello world");
+        let broken = Template::parse_template("bye {nobody}").unwrap();
+        let err = templ.render_or(&options, &broken).unwrap_err();
+        assert_eq!(err.to_string(), "Variable nobody not found");
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_out_of_range_numbers() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert(
+            "big".into(),
+            "999999999999999999999999999999999999999999".into(),
+        );
+        vars.insert("num".into(), "1.5".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        for templ in [
+            "{big:printf(%d)}",
+            "{big:words()}",
+            "{num:printf(%.70000f)}",
+            "{num:printf(%99999999999999999999f)}",
+            "{num:f(.99999)}",
+            "{num:numalign(70000)}",
+            "{num:center(70000)}",
+            "{num:comma(0)}",
+        ] {
+            let err = Template::parse_template(templ)
+                .unwrap()
+                .render(&options)
+                .unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref(),
+                    Some(errors::TransformerError::ValueOutOfRange(..))
+                        | Some(errors::TransformerError::InvalidArgumentType(
+                            _,
+                            _,
+                            "non-zero uint"
+                        ))
+                ),
+                "{templ}: {err}"
+            );
+        }
+        let templ = Template::parse_template("{num:printf(%d)}").unwrap();
+        let err = templ.render(&options).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(errors::TransformerError::InvalidValueType(..))
+        ));
+    }
 
     #[test]
-    fn test_lit() {
-        let templ = Template::parse_template("hello name").unwrap();
+    fn test_render_transformer() {
         let mut vars: HashMap<String, String> = HashMap::new();
         vars.insert("name".into(), "world".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                variables: vars,
-                ..Default::default()
-            })
-            .unwrap();
-        assert_eq!(rendered, "hello name");
+        vars.insert("snippet".into(), "{name}".into());
+        vars.insert(
+            "greeting".into(),
+            "hello {snippet:render():case(up)}".into(),
+        );
+        vars.insert("forever".into(), "{forever:render()}".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("{snippet}, {snippet:render()}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "{name}, world");
+        let templ = Template::parse_template("{greeting:render()}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "hello WORLD");
+        let templ = Template::parse_template("{forever:render()}").unwrap();
+        assert!(templ.render(&options).is_err());
     }
 
     #[test]
-    fn test_vars() {
-        let templ = Template::parse_template("hello {name}").unwrap();
+    fn test_minify() {
         let mut vars: HashMap<String, String> = HashMap::new();
         vars.insert("name".into(), "world".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                variables: vars,
-                ..Default::default()
-            })
-            .unwrap();
-        assert_eq!(rendered, "hello world");
+        let options = RenderOptions {
+            wd: PathBuf::from("."),
+            variables: vars,
+            shell_commands: true,
+            ..Default::default()
+        };
+        let templ =
+            Template::parse_template("\\{\\{hi\\}\\} {name} \\$(x\\) $(printf \\\"%s \\\" {name})")
+                .unwrap();
+        let mini = templ.minify();
+        assert_eq!(mini.parts().len(), 4);
+        assert_eq!(
+            mini.render(&options).unwrap(),
+            templ.render(&options).unwrap()
+        );
+        assert_eq!(
+            mini.part_spans()[0].0,
+            templ.part_spans()[0].0.start..templ.part_spans()[5].0.end
+        );
+        match &mini.parts()[3] {
+            TemplatePart::Cmd(parts) => assert_eq!(parts.len(), 2),
+            p => panic!("{p:?} is not a command"),
+        }
     }
 
     #[test]
-    fn test_vars_format() {
+    fn test_render_transformer_args() {
         let mut vars: HashMap<String, String> = HashMap::new();
-        vars.insert("length".into(), "120.1234".into());
-        vars.insert("name".into(), "joHN".into());
-        vars.insert("job".into(), "assistant manager of company".into());
+        vars.insert("x".into(), "hello world".into());
+        vars.insert("from".into(), "world".into());
+        vars.insert("to".into(), "there".into());
+        let mut options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("{x:repl({from},{to:case(up)})}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "hello world");
+        options.render_transformer_args = true;
+        assert_eq!(templ.render(&options).unwrap(), "hello THERE");
+        options.variables.insert("x".into(), "say {from}".into());
+        let templ = Template::parse_template("{x:repl(\\{from\\},{to})}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "say there");
+        let templ = Template::parse_template("{x:repl({missing},{from})}").unwrap();
+        assert!(templ.render(&options).is_err());
+        options.variables.insert("x".into(), "café".into());
+        options.variables.insert("e".into(), "e".into());
+        let templ = Template::parse_template("{x:repl(é,{e})}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "cafe");
+    }
+
+    #[test]
+    fn test_render_streams() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
         let options = RenderOptions {
             variables: vars,
             ..Default::default()
         };
-        let cases = [
-            ("L={length}", "L=120.1234"),
-            ("L={length:calc(+100)}", "L=220.1234"),
-            ("L={length:count(.):calc(+1)}", "L=2"),
-            ("L={length:f(.2)} ({length:f(3)})", "L=120.12 (120.123)"),
-            ("hi {name:case(up)}", "hi JOHN"),
-            (
-                "hi {name:case(proper)}, {job:case(title)}",
-                "hi John, Assistant Manager of Company",
-            ),
-            ("hi {name:case(down)}", "hi john"),
-        ];
+        let templ = Template::parse_template(
+            "header\n{>hello.txt}hello {name}{>inner.txt}inner{<}!{<}\n{>bye.txt}bye {name}",
+        )
+        .unwrap();
+        let streams = templ.render_streams(&options).unwrap();
+        assert_eq!(streams.len(), 4);
+        assert_eq!(streams[""], "header\n\n");
+        assert_eq!(streams["hello.txt"], "hello world!");
+        assert_eq!(streams["inner.txt"], "inner");
+        assert_eq!(streams["bye.txt"], "bye world");
+        let templ = Template::parse_template("hello{<}").unwrap();
+        assert!(templ.render_streams(&options).is_err());
+    }
 
-        for (t, r) in cases {
-            let templ = Template::parse_template(t).unwrap();
-            let rendered = templ.render(&options).unwrap();
-            assert_eq!(rendered, r);
+    #[test]
+    fn test_disabled_transformers() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let mut options = RenderOptions {
+            variables: vars,
+            disabled_transformers: HashSet::from(["lisp".to_string()]),
+            ..Default::default()
+        };
+        options
+            .transformer_aliases
+            .insert("shout".into(), "lisp((st+var 'self))".into());
+        let templ = Template::parse_template("{name:case(up)}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "WORLD");
+        for t in ["{name:lisp((st+var 'self))}", "{name:@shout}"] {
+            let err = Template::parse_template(t)
+                .unwrap()
+                .render(&options)
+                .unwrap_err();
+            assert!(matches!(
+                err.downcast_ref(),
+                Some(errors::TransformerError::DisabledTransformer(_))
+            ));
         }
     }
 
     #[test]
-    #[should_panic]
-    fn test_novars() {
+    fn test_render_partial() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("name".into(), "world".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("hello {name}, {age:calc(+1)} years").unwrap();
+        let (rendered, err) = templ.render_partial(&options);
+        assert_eq!(rendered, "hello world, ");
+        assert!(matches!(
+            err.unwrap().downcast_ref(),
+            Some(errors::RenderTemplateError::VariableNotFound(_))
+        ));
         let templ = Template::parse_template("hello {name}").unwrap();
-        let vars: HashMap<String, String> = HashMap::new();
-        templ
-            .render(&RenderOptions {
-                variables: vars,
-                ..Default::default()
-            })
-            .unwrap();
+        let (rendered, err) = templ.render_partial(&options);
+        assert_eq!(rendered, "hello world");
+        assert!(err.is_none());
     }
 
     #[test]
-    fn test_novars_opt() {
-        let templ = Template::parse_template("hello {name?}").unwrap();
-        let vars: HashMap<String, String> = HashMap::new();
-        let rendered = templ
-            .render(&RenderOptions {
-                variables: vars,
-                ..Default::default()
-            })
-            .unwrap();
-        assert_eq!(rendered, "hello ");
+    fn test_lisp_timeout() {
+        let mut options = RenderOptions {
+            lisp_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let templ = Template::parse_template("=(+ 1 2)").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "3");
+        let templ =
+            Template::parse_template("=(begin (define spin (lambda (x) (spin x))) (spin 1))")
+                .unwrap();
+        let err = templ.render(&options).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(errors::RenderTemplateError::LispTimeout(..))
+        ));
+        let templ = Template::parse_template(
+            "{x:lisp((begin (define spin (lambda (x) (spin x))) (spin 1)))}",
+        )
+        .unwrap();
+        options.variables.insert("x".into(), "1".into());
+        let err = templ.render(&options).unwrap_err();
+        assert!(err.to_string().contains("took longer than"));
+        options.lisp_timeout = None;
+        let templ = Template::parse_template("=(* 2 3)").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "6");
     }
 
     #[test]
-    fn test_optional() {
-        let templ = Template::parse_template("hello {age?name}").unwrap();
+    fn test_command_vars() {
+        let counter =
+            std::env::temp_dir().join(format!("stp-test-command-vars-{}", std::process::id()));
+        std::fs::write(&counter, "").unwrap();
+        let mut options = RenderOptions {
+            wd: PathBuf::from("."),
+            shell_commands: true,
+            trim_command_output: true,
+            ..Default::default()
+        };
+        options.command_vars.insert(
+            "runs".into(),
+            format!("echo x >> {0}; wc -l < {0}", counter.display()),
+        );
+        let templ = Template::parse_template("{runs}-{runs}").unwrap();
+        assert_eq!(templ.render(&options).unwrap().replace(' ', ""), "1-1");
+        // run once for all the rows
+        let rows = vec![HashMap::new(); 3];
+        for rendered in templ.render_all(&options, &rows) {
+            assert_eq!(rendered.unwrap().replace(' ', ""), "2-2");
+        }
+        options.shell_commands = false;
+        let templ = Template::parse_template("{runs:case(up)}").unwrap();
+        assert!(templ.render(&options).unwrap().starts_with("$(ECHO X"));
+        std::fs::remove_file(counter).unwrap();
+    }
+
+    #[test]
+    fn test_empty_fallback() {
         let mut vars: HashMap<String, String> = HashMap::new();
-        vars.insert("name".into(), "world".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                variables: vars,
-                ..Default::default()
-            })
-            .unwrap();
-        assert_eq!(rendered, "hello world");
+        vars.insert("name".into(), "".into());
+        vars.insert("nick".into(), "bob".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("[{name?nick}]").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "[]");
+        let templ = Template::parse_template("[{name??nick}]").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "[bob]");
+        assert_eq!(templ.parts()[1].to_string(), "name??nick");
+        assert_eq!(templ.parts()[1].variables(), ["name", "nick"]);
+        assert!(
+            matches!(&templ.parts()[1], TemplatePart::Any(a) if a[1] == TemplatePart::SkipEmpty)
+        );
+        // a blank alternative is an empty value, not `??`
+        let templ = Template::parse_template("[{name? ?nick}]").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "[]");
+        let templ = Template::parse_template("[{name??missing?\"x\"}]").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "[x]");
+        let templ = Template::parse_template("[{name?}]").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "[]");
     }
 
     #[test]
-    fn test_special_chars() {
-        let templ = Template::parse_template("$hello {}? \\{\\}%").unwrap();
-        let rendered = templ.render(&RenderOptions::default()).unwrap();
-        assert_eq!(rendered, "$hello ? {}%");
+    fn test_cmd_lisp() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("n".into(), "4".into());
+        let options = RenderOptions {
+            wd: PathBuf::from("."),
+            variables: vars,
+            shell_commands: true,
+            trim_command_output: true,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("$(echo =(* 2 (st+num 'n)))").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "8");
+        let templ = Template::parse_template("$(echo \\\"=(+ 1 2)\\\")").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "3");
     }
 
     #[test]
-    fn test_special_chars2() {
-        let templ = Template::parse_template("$hello {}? \"{\"\"}\"%").unwrap();
-        let rendered = templ.render(&RenderOptions::default()).unwrap();
-        assert_eq!(rendered, "$hello ? {}%");
+    fn test_quoted_transformer_args() {
+        let mut vars: HashMap<String, String> = HashMap::new();
+        vars.insert("items".into(), "a,b".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("{items:enumerate(,,\"%d) \")}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "1) a\n2) b");
     }
 
     #[test]
-    fn test_optional_lit() {
-        let templ = Template::parse_template("hello {age?\"20\"}").unwrap();
+    fn test_reject_non_finite() {
         let mut vars: HashMap<String, String> = HashMap::new();
-        vars.insert("name".into(), "world".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                variables: vars,
-                ..Default::default()
-            })
-            .unwrap();
-        assert_eq!(rendered, "hello 20");
+        vars.insert("num".into(), "1".into());
+        let mut options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let templ = Template::parse_template("{num:calc(/0)}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "inf");
+        options.reject_non_finite = true;
+        assert!(templ.render(&options).is_err());
+        let templ = Template::parse_template("{num:calc(/2):f(2)}").unwrap();
+        assert_eq!(templ.render(&options).unwrap(), "0.50");
     }
 
     #[test]
-    fn test_command() {
-        let templ = Template::parse_template("hello $(echo {name})").unwrap();
+    fn test_render_tracked() {
+        let templ = Template::parse_template("{name?nickname} is {age?\"unknown\"}").unwrap();
         let mut vars: HashMap<String, String> = HashMap::new();
         vars.insert("name".into(), "world".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                wd: PathBuf::from("."),
-                variables: vars,
-                shell_commands: true,
-            })
-            .unwrap();
-        assert_eq!(rendered, "hello world\n");
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        let (rendered, used) = templ.render_tracked(&options).unwrap();
+        assert_eq!(rendered, "world is unknown");
+        assert!(used.contains("name"));
+        assert!(!used.contains("nickname"));
+        assert!(used.contains("age"));
+        assert_eq!(used.len(), 2);
     }
 
     #[test]
-    fn test_command_quote() {
-        let templ = Template::parse_template("hello $(printf \\\"%s %d\\\" {name} {age})").unwrap();
+    fn test_transformer_aliases() {
+        let templ =
+            Template::parse_template("{price:@money} {price:@money:q($)} {x:@nope}").unwrap();
         let mut vars: HashMap<String, String> = HashMap::new();
-        vars.insert("name".into(), "world".into());
-        vars.insert("age".into(), "1".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                wd: PathBuf::from("."),
-                variables: vars,
-                shell_commands: true,
-            })
-            .unwrap();
-        assert_eq!(rendered, "hello world 1");
+        vars.insert("price".into(), "1234.5".into());
+        vars.insert("x".into(), "1".into());
+        let mut options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        options
+            .transformer_aliases
+            .insert("money".into(), "f(.2,group)".into());
+        assert!(templ.check_transformers().is_ok());
+        assert!(templ.render(&options).is_err());
+        options
+            .transformer_aliases
+            .insert("nope".into(), "@money".into());
+        assert_eq!(templ.render(&options).unwrap(), "1,234.50 $1,234.50$ 1.00");
+        options
+            .transformer_aliases
+            .insert("money".into(), "@nope".into());
+        assert!(templ.render(&options).is_err());
     }
 
     #[test]
-    fn test_time() {
-        let templ = Template::parse_template("hello {name} at {%Y-%m-%d}").unwrap();
-        let timefmt = Local::now().format("%Y-%m-%d");
-        let output = format!("hello world at {}", timefmt);
+    fn test_empty_is_missing() {
+        let templ = Template::parse_template("hello {name?other}").unwrap();
         let mut vars: HashMap<String, String> = HashMap::new();
-        vars.insert("name".into(), "world".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                wd: PathBuf::from("."),
-                variables: vars,
-                shell_commands: false,
-            })
-            .unwrap();
-        assert_eq!(rendered, output);
+        vars.insert("name".into(), "".into());
+        vars.insert("other".into(), "world".into());
+        let mut options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        assert_eq!(templ.render(&options).unwrap(), "hello ");
+        options.empty_is_missing = true;
+        assert_eq!(templ.render(&options).unwrap(), "hello world");
+        options.variables.insert("other".into(), "".into());
+        assert!(templ.render(&options).is_err());
     }
 
     #[test]
-    fn test_var_or_time() {
-        let templ = Template::parse_template("hello {name} at {age?%Y-%m-%d}").unwrap();
-        let timefmt = Local::now().format("%Y-%m-%d");
-        let output = format!("hello world at {}", timefmt);
+    fn test_render_json_string() {
+        let templ = Template::parse_template("{a}\\\\{b}").unwrap();
         let mut vars: HashMap<String, String> = HashMap::new();
-        vars.insert("name".into(), "world".into());
-        let rendered = templ
-            .render(&RenderOptions {
-                wd: PathBuf::from("."),
-                variables: vars,
-                shell_commands: false,
-            })
-            .unwrap();
-        assert_eq!(rendered, output);
+        vars.insert("a".into(), "say \"hi\"\nnow".into());
+        vars.insert("b".into(), "C:\\tmp\t\u{1}".into());
+        let options = RenderOptions {
+            variables: vars,
+            ..Default::default()
+        };
+        assert_eq!(
+            templ.render_json_string(&options).unwrap(),
+            r#""say \"hi\"\nnow\\C:\\tmp\t\u0001""#
+        );
     }
 
     #[test]
-    fn test_render_iter() {
+    fn test_has_parts() {
+        let templ = Template::parse_template("{name} {x?%F} =(+ 1 2)").unwrap();
+        assert!(!templ.has_commands());
+        assert!(templ.has_lisp());
+        assert!(templ.has_time());
+        let templ = Template::parse_template("$(echo {name?=(+ 1 2)})").unwrap();
+        assert!(templ.has_commands());
+        assert!(templ.has_lisp());
+        assert!(!templ.has_time());
         let templ = Template::parse_template("hello {name}").unwrap();
+        assert!(!templ.has_commands());
+        assert!(!templ.has_lisp());
+        assert!(!templ.has_time());
+    }
+
+    #[test]
+    fn test_invalid_time_format() {
+        assert!(Template::parse_template("today is {%Y-%m-%d}").is_ok());
+        let err = Template::parse_template("today is {%Y-%Q}").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(errors::RenderTemplateError::InvalidFormat(..))
+        ));
+        assert!(Template::parse_template("today is {x?%5}").is_err());
+        let part = TemplatePart::time("%Q");
+        assert!(part.render(&RenderOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_render_range() {
+        let templ = Template::parse_template("{name}-{i:calc(*2)}").unwrap();
         let mut vars: HashMap<String, String> = HashMap::new();
-        vars.insert("name".into(), "world".into());
+        vars.insert("name".into(), "part".into());
         let options = RenderOptions {
             variables: vars,
             ..Default::default()
         };
-        let mut names = options.render_iter(&templ);
-        assert_eq!("hello world-1", names.next().unwrap());
-        assert_eq!("hello world-2", names.next().unwrap());
-        assert_eq!("hello world-3", names.next().unwrap());
+        let rendered: Vec<String> = options
+            .render_range(&templ, "i", 1..=3)
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(rendered, ["part-2", "part-4", "part-6"]);
+        let mut names = options.render_range(&templ, "i", ["a"]);
+        assert!(names.next().unwrap().is_err());
+        assert!(names.next().is_none());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_variables_from_json() {
+        let path = std::env::temp_dir().join(format!("stp-vars-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"name": "world", "age": 30, "ok": true, "none": null,
+                "user": {"langs": ["rust", "lisp"], "height": 1.5}}"#,
+        )
+        .unwrap();
+        let vars = RenderOptions::variables_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vars["name"], "world");
+        assert_eq!(vars["age"], "30");
+        assert_eq!(vars["ok"], "true");
+        assert_eq!(vars["none"], "");
+        assert_eq!(vars["user.langs.1"], "lisp");
+        assert_eq!(vars["user.height"], "1.5");
+        assert_eq!(vars.len(), 7);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_variables_from_toml() {
+        let path = std::env::temp_dir().join(format!("stp-vars-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "name = \"world\"\nage = 30\n[user]\nlangs = [\"rust\"]\n",
+        )
+        .unwrap();
+        let vars = RenderOptions::variables_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vars["name"], "world");
+        assert_eq!(vars["age"], "30");
+        assert_eq!(vars["user.langs.0"], "rust");
+        assert!(RenderOptions::variables_from_file("vars.yaml").is_err());
     }
 }
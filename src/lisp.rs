@@ -4,12 +4,16 @@ use rust_lisp::interpreter::eval_block;
 use rust_lisp::model::{FloatType, RuntimeError, Symbol, Value};
 use rust_lisp::parser::{parse, ParseError};
 use std::num::ParseFloatError;
+use std::sync::mpsc;
+use std::time::Duration;
 use std::{
     cell::RefCell,
     collections::{HashMap, HashSet},
     rc::Rc,
 };
 
+use crate::errors::RenderTemplateError;
+
 /// Evaluate the lisp expression
 ///
 ///
@@ -29,6 +33,70 @@ use std::{
 /// # Ok(())
 /// # }
 pub fn calculate(variables: &HashMap<String, String>, expr: &str) -> anyhow::Result<String> {
+    Ok(evaluate(variables, expr)?.to_string())
+}
+
+/// Evaluate the lisp expression in a separate thread, and fail if it
+/// doesn't finish within the timeout. The result is given as text,
+/// with string results without the quotes. As the evaluation can't be
+/// interrupted, the thread keeps running in the background after
+/// the timeout.
+///
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::time::Duration;
+/// # use string_template_plus::lisp::*;
+/// # use std::collections::HashMap;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("test".into(), "1".into());
+///     let timeout = Duration::from_secs(1);
+///     assert_eq!(evaluate_timeout(&vars, "(+ 1 1)", timeout)?, "2");
+///     assert_eq!(evaluate_timeout(&vars, "(st+var 'test)", timeout)?, "1");
+/// # Ok(())
+/// # }
+/// ```
+pub fn evaluate_timeout(
+    variables: &HashMap<String, String>,
+    expr: &str,
+    timeout: Duration,
+) -> anyhow::Result<String> {
+    let (tx, rx) = mpsc::channel();
+    let vars = variables.clone();
+    let e = expr.to_string();
+    std::thread::spawn(move || {
+        // lisp values can't be sent across threads
+        let res = evaluate(&vars, &e).map(|v| match v {
+            Value::String(s) => s,
+            v => v.to_string(),
+        });
+        // the receiver is gone if it timed out
+        let _ = tx.send(res);
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(res) => res,
+        Err(_) => Err(RenderTemplateError::LispTimeout(expr.to_string(), timeout).into()),
+    }
+}
+
+/// Evaluate the lisp expression and return the resulting lisp [`Value`]
+///
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::lisp::*;
+/// # use std::collections::HashMap;
+/// # use rust_lisp::model::Value;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("test".into(), "1".into());
+///     assert_eq!(evaluate(&vars, "(st+var 'test)")?, Value::String("1".into()));
+/// # Ok(())
+/// # }
+pub fn evaluate(variables: &HashMap<String, String>, expr: &str) -> anyhow::Result<Value> {
     let expr = parse(expr)
         .collect::<Result<Vec<Value>, ParseError>>()
         .ok()
@@ -94,7 +162,7 @@ pub fn calculate(variables: &HashMap<String, String>, expr: &str) -> anyhow::Res
         }))),
     );
 
-    let vars3: HashSet<String> = variables.iter().map(|(k, _)| k.to_string()).collect();
+    let vars3: HashSet<String> = variables.keys().map(|k| k.to_string()).collect();
     env.borrow_mut().define(
         Symbol::from("st+has"),
         Value::NativeClosure(Rc::new(RefCell::new(move |_, args: Vec<Value>| {
@@ -105,7 +173,7 @@ pub fn calculate(variables: &HashMap<String, String>, expr: &str) -> anyhow::Res
                     msg: "Only Symbol and String can be passed to st+num.".into(),
                 })?,
             };
-            Ok(vars3.get(&name).is_some().into())
+            Ok(vars3.contains(&name).into())
         }))),
     );
 
@@ -118,6 +186,5 @@ pub fn calculate(variables: &HashMap<String, String>, expr: &str) -> anyhow::Res
     //     },
     // );
 
-    let res = eval_block(env.clone(), expr.into_iter())?;
-    Ok(res.to_string())
+    Ok(eval_block(env.clone(), expr.into_iter())?)
 }
@@ -1,55 +1,429 @@
 /// Transformers for the template
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::num::IntErrorKind;
 use std::ops::{Bound, RangeBounds};
+use std::time::Duration;
 
 use crate::errors::TransformerError;
-use crate::VAR_TRANSFORM_SEP_CHAR;
+use crate::{
+    lisp, remove_escapes, split_unescaped, RenderOptions, TemplatePart, Variables, ESCAPE_CHAR,
+    VAR_TRANSFORM_SEP_CHAR,
+};
 use lazy_static::lazy_static;
 use regex::Regex;
 use titlecase::titlecase;
+use unicode_normalization::UnicodeNormalization;
+
+/// Function that transforms the value with the given arguments
+pub type TransformerFn = fn(&str, Vec<&str>) -> Result<String, TransformerError>;
+
+/// Information about a transformer, useful for tooling like autocomplete
+#[derive(Debug, Clone)]
+pub struct TransformerSpec {
+    /// Name used to call the transformer in the template
+    pub name: &'static str,
+    /// Minimum number of arguments
+    pub min_args: usize,
+    /// Maximum number of arguments, `None` if there is no limit
+    pub max_args: Option<usize>,
+    /// Short description of what the transformer does
+    pub description: &'static str,
+    /// Function that does the transformation
+    pub func: TransformerFn,
+}
+
+impl TransformerSpec {
+    const fn new(
+        name: &'static str,
+        min_args: usize,
+        max_args: Option<usize>,
+        description: &'static str,
+        func: TransformerFn,
+    ) -> Self {
+        Self {
+            name,
+            min_args,
+            max_args,
+            description,
+            func,
+        }
+    }
+}
+
+/// All the built-in transformers, [`apply_tranformers`] uses this to
+/// find the transformer to call
+const TRANSFORMERS: &[TransformerSpec] = &[
+    TransformerSpec::new("f", 1, Some(2), "format float", float_format),
+    TransformerSpec::new("printf", 1, Some(1), "C style printf format", printf),
+    TransformerSpec::new("sig", 1, Some(1), "round to significant figures", sig),
+    TransformerSpec::new("numalign", 1, Some(3), "right align numbers", num_align),
+    TransformerSpec::new("center", 1, Some(2), "center within width", center),
+    TransformerSpec::new("case", 1, Some(2), "change case", string_case),
+    TransformerSpec::new("calc", 1, None, "arithmetic calculation", calc),
+    TransformerSpec::new("math", 1, None, "calculation with precedence", math),
+    TransformerSpec::new("count", 1, None, "count pattern occurrences", count),
+    TransformerSpec::new("wc", 1, None, "count words, lines or chars", word_count),
+    TransformerSpec::new("repl", 2, Some(3), "replace text", replace),
+    TransformerSpec::new("col", 1, Some(2), "take Nth column", col),
+    TransformerSpec::new("take", 2, Some(3), "take Nth group", take),
+    TransformerSpec::new("trim", 0, None, "trim both sides", trim),
+    TransformerSpec::new("oneline", 0, Some(2), "join lines into one", oneline),
+    TransformerSpec::new("ltrim", 0, None, "trim left side", trim_left),
+    TransformerSpec::new("rtrim", 0, None, "trim right side", trim_right),
+    TransformerSpec::new("stripprefix", 1, None, "remove the prefix", strip_prefix),
+    TransformerSpec::new("stripsuffix", 1, None, "remove the suffix", strip_suffix),
+    TransformerSpec::new("comma", 1, None, "insert commas", comma),
+    TransformerSpec::new("group", 1, None, "insert separators", group),
+    TransformerSpec::new("q", 0, Some(2), "quote the text", quote),
+    TransformerSpec::new("unquote", 0, Some(1), "remove the quotes", unquote),
+    TransformerSpec::new("unescape", 0, Some(1), "interpret escapes", unescape),
+    TransformerSpec::new("path", 1, None, "convert path separators", path_sep),
+    TransformerSpec::new("ext", 0, Some(1), "file extension", extension),
+    TransformerSpec::new("basename", 0, Some(1), "last path component", basename),
+    TransformerSpec::new("dirname", 0, Some(1), "parent directory", dirname),
+    TransformerSpec::new("oxford", 0, Some(3), "list as English prose", oxford),
+    TransformerSpec::new("enumerate", 0, Some(4), "list as numbered lines", enumerate),
+    TransformerSpec::new("field", 1, Some(3), "value of key=value pair", field),
+    TransformerSpec::new("words", 0, Some(1), "number in English words", words),
+    TransformerSpec::new("isodur", 0, Some(1), "ISO 8601 durations", iso_duration),
+    TransformerSpec::new("plural", 2, Some(3), "singular or plural form", plural),
+    TransformerSpec::new("dateoffset", 2, Some(3), "shift the date", date_offset),
+    TransformerSpec::new("ago", 1, Some(1), "time since the date in words", ago),
+    TransformerSpec::new("reformat", 2, None, "regex groups into format", reformat),
+    TransformerSpec::new("when", 2, Some(3), "value if matches", when),
+    TransformerSpec::new("showif", 1, Some(1), "value if variable is truthy", show_if),
+    TransformerSpec::new("deaccent", 0, Some(1), "remove diacritical marks", deaccent),
+    TransformerSpec::new("luhn", 0, Some(1), "Luhn check digit", luhn),
+    TransformerSpec::new("pick", 1, None, "pick argument by index", pick),
+    TransformerSpec::new("cmp", 4, Some(4), "branch on comparison", cmp),
+    TransformerSpec::new("once", 0, Some(1), "value only the first time", once),
+    TransformerSpec::new("bar", 1, Some(3), "progress bar for percentage", bar),
+    TransformerSpec::new("keep", 1, None, "keep characters of a class", keep),
+    TransformerSpec::new("strip", 1, None, "remove characters of a class", strip),
+    TransformerSpec::new("dbg", 0, Some(1), "debug info of the value", dbg),
+    TransformerSpec::new(
+        "accounting",
+        0,
+        Some(1),
+        "negatives in parentheses",
+        accounting,
+    ),
+    TransformerSpec::new("hexdump", 0, Some(1), "hex dump of the bytes", hexdump),
+    TransformerSpec::new("bucket", 1, Some(1), "stable hash bucket index", bucket),
+    TransformerSpec::new("levenshtein", 1, None, "edit distance", levenshtein),
+    TransformerSpec::new("render", 0, Some(1), "render as template", render_template),
+    TransformerSpec::new("lisp", 1, None, "evaluate lisp with self", lisp_transform),
+];
+
+/// Names of all the built-in transformers, in the same order as
+/// [`transformer_specs`]
+///
+/// ```rust
+/// # use string_template_plus::transformers::*;
+///     assert!(BUILTIN_TRANSFORMERS.contains(&"calc"));
+///     assert_eq!(BUILTIN_TRANSFORMERS.len(), transformer_specs().len());
+/// ```
+pub const BUILTIN_TRANSFORMERS: &[&str] = &{
+    let mut names = [""; TRANSFORMERS.len()];
+    let mut i = 0;
+    while i < names.len() {
+        names[i] = TRANSFORMERS[i].name;
+        i += 1;
+    }
+    names
+};
+
+/// Whether there is a built-in transformer with the name, the aliases
+/// (like `@money`) are not built-in
+///
+/// ```rust
+/// # use string_template_plus::transformers::*;
+///     assert!(is_builtin_transformer("case"));
+///     assert!(!is_builtin_transformer("nope"));
+///     assert!(!is_builtin_transformer("@money"));
+/// ```
+pub fn is_builtin_transformer(name: &str) -> bool {
+    BUILTIN_TRANSFORMERS.contains(&name)
+}
+
+/// Specifications of all the built-in transformers
+///
+/// ```rust
+/// # use string_template_plus::transformers::*;
+///     let specs = transformer_specs();
+///     let f = specs.iter().find(|s| s.name == "f").unwrap();
+///     assert_eq!((f.min_args, f.max_args), (1, Some(2)));
+/// ```
+pub fn transformer_specs() -> Vec<TransformerSpec> {
+    TRANSFORMERS.to_vec()
+}
+
+/// Specification of the built-in transformer with the given name
+pub fn transformer_spec(name: &str) -> Option<&'static TransformerSpec> {
+    TRANSFORMERS.iter().find(|t| t.name == name)
+}
+
+/// Prefix for the transformer aliases, see [`expand_aliases`]
+pub static TRANSFORMER_ALIAS_CHAR: char = '@';
+
+/// Splits the transformers string into each transformer. They are
+/// only separated at the [`VAR_TRANSFORM_SEP_CHAR`] right after the
+/// closing parenthesis or the alias name, so the arguments can have
+/// it, like in `when(re:^5,error)`.
+fn split_transformers(transformations: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut prev = None;
+    for (i, c) in transformations.char_indices() {
+        if prev == Some(ESCAPE_CHAR) {
+            // escaped characters don't end the transformer
+            prev = None;
+            continue;
+        }
+        if c == VAR_TRANSFORM_SEP_CHAR
+            && (prev == Some(')') || transformations[start..].starts_with(TRANSFORMER_ALIAS_CHAR))
+        {
+            parts.push(&transformations[start..i]);
+            start = i + c.len_utf8();
+        }
+        prev = Some(c);
+    }
+    parts.push(&transformations[start..]);
+    parts.retain(|t| !t.is_empty());
+    parts
+}
+
+/// Splits the transformers string into the name and arguments of
+/// each transformer, see [`split_transformers`]. Aliases are given
+/// with their name (including [`TRANSFORMER_ALIAS_CHAR`]) and no
+/// arguments. The arguments are split at the commas that are not
+/// escaped, and keep the escapes as they are, they are removed just
+/// before the arguments are given to the transformers.
+///
+/// ```rust
+/// # use string_template_plus::transformers::*;
+///     let parsed = parse_transformers("when(re:^5,error):@money:case(up)").unwrap();
+///     assert_eq!(
+///         parsed,
+///         [("when", vec!["re:^5", "error"]), ("@money", vec![]), ("case", vec!["up"])]
+///     );
+///     let parsed = parse_transformers("repl(\\,,\\)):case(up)").unwrap();
+///     assert_eq!(parsed, [("repl", vec!["\\,", "\\)"]), ("case", vec!["up"])]);
+/// ```
+pub fn parse_transformers(
+    transformations: &str,
+) -> Result<Vec<(&str, Vec<&str>)>, TransformerError> {
+    split_transformers(transformations)
+        .into_iter()
+        .map(|tstr| {
+            if tstr.starts_with(TRANSFORMER_ALIAS_CHAR) {
+                return Ok((tstr, vec![]));
+            }
+            let (name, args) = tstr.split_once('(').ok_or(TransformerError::InvalidSyntax(
+                tstr.to_string(),
+                "No opening paranthesis".to_string(),
+            ))?;
+            let args = args
+                .strip_suffix(')')
+                .ok_or(TransformerError::InvalidSyntax(
+                    tstr.to_string(),
+                    "No closing paranthesis".to_string(),
+                ))?;
+            Ok((name, split_unescaped(args, ',')))
+        })
+        .collect()
+}
+
+/// Replaces the aliases (`@name`) in the transformers with the
+/// transformers they stand for, aliases can use other aliases.
+/// Unknown aliases are left as they are, and fail when applied.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let mut aliases: HashMap<String, String> = HashMap::new();
+///     aliases.insert("money".into(), "f(.2):group(,,3)".into());
+///     aliases.insert("usd".into(), "@money:q($)".into());
+///     assert_eq!(expand_aliases("@usd:trim()", &aliases)?, "f(.2):group(,,3):q($):trim()");
+///     aliases.insert("loop".into(), "@usd:@loop".into());
+///     assert!(expand_aliases("@loop", &aliases).is_err());
+/// # Ok(())
+/// # }
+pub fn expand_aliases(
+    transformations: &str,
+    aliases: &HashMap<String, String>,
+) -> Result<String, TransformerError> {
+    fn expand(
+        transformations: &str,
+        aliases: &HashMap<String, String>,
+        stack: &mut Vec<String>,
+    ) -> Result<String, TransformerError> {
+        let parts = split_transformers(transformations)
+            .into_iter()
+            .map(|t| match t.strip_prefix(TRANSFORMER_ALIAS_CHAR) {
+                Some(name) if aliases.contains_key(name) => {
+                    if stack.iter().any(|s| s == name) {
+                        return Err(TransformerError::InvalidSyntax(
+                            t.to_string(),
+                            format!("Recursive transformer alias {}", stack.join(" -> ")),
+                        ));
+                    }
+                    stack.push(name.to_string());
+                    let expanded = expand(&aliases[name], aliases, stack)?;
+                    stack.pop();
+                    Ok(expanded)
+                }
+                _ => Ok(t.to_string()),
+            })
+            .collect::<Result<Vec<String>, TransformerError>>()?;
+        Ok(parts.join(&VAR_TRANSFORM_SEP_CHAR.to_string()))
+    }
+    if aliases.is_empty() {
+        return Ok(transformations.to_string());
+    }
+    expand(transformations, aliases, &mut Vec::new())
+}
+
+/// Checks the transformers exist and have the correct number of
+/// arguments without applying them, `val` is only used in the error
+/// message.
+///
+/// ```rust
+/// # use string_template_plus::transformers::*;
+///     assert!(check_transformers("name", "case(up):f(2)").is_ok());
+///     assert!(check_transformers("name", "case(up):nope()").is_err());
+///     assert!(check_transformers("name", "repl(a)").is_err());
+/// ```
+pub fn check_transformers(val: &str, transformations: &str) -> Result<(), TransformerError> {
+    for (name, args) in parse_transformers(transformations)? {
+        if name.starts_with(TRANSFORMER_ALIAS_CHAR) {
+            // aliases are only known while rendering
+            continue;
+        }
+        let spec = transformer_spec(name).ok_or(TransformerError::UnknownTranformer(
+            name.to_string(),
+            val.to_string(),
+        ))?;
+        let max = spec.max_args.map_or(Bound::Unbounded, Bound::Included);
+        check_arguments_len(spec.name, (Bound::Included(spec.min_args), max), args.len())?;
+    }
+    Ok(())
+}
 
 /// Applies any tranformations to the variable, you can chain the
 /// transformers called whenever you use [`VAR_TRANSFORM_SEP_CHAR`] to
 /// provide a transformer in the template.
 pub fn apply_tranformers(val: &str, transformations: &str) -> Result<String, TransformerError> {
+    apply_tranformers_with_vars(val, transformations, &HashMap::new())
+}
+
+/// Same as [`apply_tranformers`] but the `lisp` and `showif`
+/// transformers have access to the given variables.
+pub fn apply_tranformers_with_vars(
+    val: &str,
+    transformations: &str,
+    variables: &HashMap<String, String>,
+) -> Result<String, TransformerError> {
+    let op = RenderOptions {
+        variables: variables.clone(),
+        ..Default::default()
+    };
+    apply_tranformers_with_options(val, transformations, &op)
+}
+
+/// Same as [`apply_tranformers_with_vars`] with the variables and
+/// other settings from the [`RenderOptions`], this is what is used
+/// while rendering the template.
+pub fn apply_tranformers_with_options(
+    val: &str,
+    transformations: &str,
+    op: &RenderOptions,
+) -> Result<String, TransformerError> {
+    apply_tranformers_with_source(val, transformations, op, &op.variables)
+}
+
+/// Same as [`apply_tranformers_with_options`] with the values of the
+/// variables from `vars`, see [`crate::Template::render_with`]
+pub(crate) fn apply_tranformers_with_source(
+    val: &str,
+    transformations: &str,
+    op: &RenderOptions,
+    vars: &dyn Variables,
+) -> Result<String, TransformerError> {
     let mut val: String = val.to_string();
-    for tstr in transformations.split(VAR_TRANSFORM_SEP_CHAR) {
-        if tstr.is_empty() {
-            continue;
-        }
-        let (name, args) = tstr.split_once('(').ok_or(TransformerError::InvalidSyntax(
-            tstr.to_string(),
-            "No opening paranthesis".to_string(),
+    for (name, args) in parse_transformers(transformations)? {
+        let args: Vec<Cow<str>> = args.into_iter().map(remove_escapes).collect();
+        let args: Vec<&str> = args.iter().map(|a| a.as_ref()).collect();
+        let spec = transformer_spec(name).ok_or(TransformerError::UnknownTranformer(
+            name.to_string(),
+            val.to_string(),
         ))?;
-        let args: Vec<&str> = args
-            .strip_suffix(')')
-            .ok_or(TransformerError::InvalidSyntax(
-                tstr.to_string(),
-                "No closing paranthesis".to_string(),
-            ))?
-            .split(',')
-            .collect();
-        val = match name {
-            "f" => float_format(&val, args)?,
-            "case" => string_case(&val, args)?,
-            "calc" => calc(&val, args)?,
-            "count" => count(&val, args)?,
-            "repl" => replace(&val, args)?,
-            "take" => take(&val, args)?,
-            "trim" => trim(&val, args)?,
-            "comma" => comma(&val, args)?,
-            "group" => group(&val, args)?,
-            "q" => quote(&val, args)?,
-            _ => {
-                return Err(TransformerError::UnknownTranformer(
-                    name.to_string(),
-                    val.to_string(),
-                ))
+        if op.disabled_transformers.contains(spec.name) {
+            return Err(TransformerError::DisabledTransformer(name.to_string()));
+        }
+        val = match spec.name {
+            "lisp" => {
+                let lisp_vars = TemplatePart::lisp_variables(&args.join(","), vars, op);
+                match op.lisp_timeout {
+                    Some(t) => lisp_transform_timeout(&val, args, &lisp_vars, t)?,
+                    None => lisp_transform_with_vars(&val, args, &lisp_vars)?,
+                }
             }
+            "showif" => show_if_with_vars(&val, args, vars)?,
+            "ago" => match op.now {
+                Some(now) => ago_since(&val, args, now.naive_local())?,
+                None => ago(&val, args)?,
+            },
+            "render" => render_template_with_source(&val, args, op, vars)?,
+            _ => (spec.func)(&val, args)?,
         };
+        if op.reject_non_finite && NUMERIC_TRANSFORMERS.contains(&spec.name) {
+            check_finite(spec.name, &val)?;
+        }
     }
     Ok(val)
 }
 
+/// Transformers that give numbers, these are checked with
+/// [`check_finite`] when [`RenderOptions::reject_non_finite`] is set
+const NUMERIC_TRANSFORMERS: &[&str] = &["f", "printf", "numalign", "calc", "math", "sig"];
+
+/// Check that none of the comma separated numbers in the output of
+/// the transformer are `NaN` or infinite
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert!(check_finite("calc", "1.5,2").is_ok());
+///     assert!(check_finite("calc", "hello").is_ok());
+///     assert!(check_finite("calc", "1,inf").is_err());
+///     assert!(check_finite("f", "  NaN").is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn check_finite(func_name: &'static str, val: &str) -> Result<(), TransformerError> {
+    for num in val.split(',') {
+        if let Ok(n) = num.trim().parse::<f64>() {
+            if !n.is_finite() {
+                return Err(TransformerError::ValueOutOfRange(
+                    func_name,
+                    num.trim().to_string(),
+                    "finite",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Gets the bound of a rust range object
 ///
 /// ```rust
@@ -73,6 +447,49 @@ pub fn bound(b: Bound<&usize>, lower: bool) -> Option<usize> {
     }
 }
 
+/// Converts the windows (`\r\n`) and old mac (`\r`) line endings
+/// to `\n`, if the pattern has a newline in it. Used by the
+/// transformers that split by lines.
+fn normalize_newlines<'a>(val: &'a str, pattern: &str) -> Cow<'a, str> {
+    if pattern.contains('\n') && val.contains('\r') {
+        Cow::Owned(val.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(val)
+    }
+}
+
+/// Largest width or precision allowed while formatting the values
+const MAX_FORMAT_WIDTH: usize = u16::MAX as usize;
+
+/// Parse the width or precision argument for formatting, which can't
+/// be more than [`MAX_FORMAT_WIDTH`]
+fn parse_format_width(func_name: &'static str, arg: &str) -> Result<usize, TransformerError> {
+    let out_of_range =
+        || TransformerError::ValueOutOfRange(func_name, arg.to_string(), "at most 65535");
+    match arg.parse::<usize>() {
+        Ok(width) if width <= MAX_FORMAT_WIDTH => Ok(width),
+        Ok(_) => Err(out_of_range()),
+        Err(e) if *e.kind() == IntErrorKind::PosOverflow => Err(out_of_range()),
+        Err(_) => Err(TransformerError::InvalidArgumentType(
+            func_name,
+            arg.to_string(),
+            "uint",
+        )),
+    }
+}
+
+/// Parse the value as an integer, the ones too big for [`i128`] are
+/// out of range instead of invalid
+fn parse_integer(func_name: &'static str, val: &str) -> Result<i128, TransformerError> {
+    val.parse()
+        .map_err(|e: std::num::ParseIntError| match e.kind() {
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                TransformerError::ValueOutOfRange(func_name, val.to_string(), "a 128 bit integer")
+            }
+            _ => TransformerError::InvalidValueType(func_name, "integer"),
+        })
+}
+
 /// Checks whether the arguments lenth matches what is required
 fn check_arguments_len<R: RangeBounds<usize>>(
     func_name: &'static str,
@@ -102,6 +519,9 @@ fn check_arguments_len<R: RangeBounds<usize>>(
 
 /// format the float (numbers). For example with `val=1.123`, `{val:f(2)}` or `{val:f(.2)}` gives `1.12`
 ///
+/// Give `group` as the second argument to group the digits of the
+/// integer part in thousands. e.g. `{val:f(.2,group)}`
+///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
@@ -110,11 +530,16 @@ fn check_arguments_len<R: RangeBounds<usize>>(
 ///     assert_eq!(float_format("1.12", vec![".1"])?, "1.1");
 ///     assert_eq!(float_format("1.12", vec!["2"])?, "1.12");
 ///     assert_eq!(float_format("1.12", vec!["0"])?, "1");
+///     assert_eq!(float_format("1234.567", vec![".2"])?, "1234.57");
+///     assert_eq!(float_format("1234.567", vec![".2", "group"])?, "1,234.57");
+///     assert_eq!(float_format("-1234567.8", vec!["0", "group"])?, "-1,234,568");
+///     assert_eq!(float_format("123.4", vec![".1", "group"])?, "123.4");
+///     assert!(float_format("1.5", vec![".70000"]).is_err());
 /// # Ok(())
 /// # }
 pub fn float_format(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
     let func_name = "f";
-    check_arguments_len(func_name, 1..=1, args.len())?;
+    check_arguments_len(func_name, 1..=2, args.len())?;
     let format = args[0];
     let val = val
         .parse::<f64>()
@@ -123,352 +548,2993 @@ pub fn float_format(val: &str, args: Vec<&str>) -> Result<String, TransformerErr
     let mut decimal = 6usize;
     if let Some((d, f)) = format.split_once('.') {
         if !d.is_empty() {
-            start = d.parse().map_err(|_| {
-                TransformerError::InvalidArgumentType(func_name, d.to_string(), "uint")
-            })?;
+            start = parse_format_width(func_name, d)?;
         }
         if f.is_empty() {
             decimal = 0;
         } else {
-            decimal = f.parse().map_err(|_| {
-                TransformerError::InvalidArgumentType(func_name, f.to_string(), "uint")
-            })?;
+            decimal = parse_format_width(func_name, f)?;
         }
     } else if !format.is_empty() {
-        decimal = format.parse().map_err(|_| {
-            TransformerError::InvalidArgumentType(func_name, format.to_string(), "uint")
-        })?;
+        decimal = parse_format_width(func_name, format)?;
+    }
+    let formatted = format!("{0:1$.2$}", val, start, decimal);
+    match args.get(1) {
+        None => Ok(formatted),
+        Some(&"group") => group_integer_part(&formatted, vec![",", "3"]),
+        Some(g) => Err(TransformerError::InvalidArgumentType(
+            func_name,
+            g.to_string(),
+            "{group}",
+        )),
     }
-    Ok(format!("{0:1$.2$}", val, start, decimal))
 }
 
-/// Format the string. Supports `up`=> UPCASE, `down`=> downcase, `proper` => first character UPCASE all others downcase, `title` => title case according to [`titlecase::titlecase`]. e.g. `{var:case(up)}`.
+/// Group the digits of the integer part of a number using [`group`],
+/// leaving the sign and the decimal part as they are.
+fn group_integer_part(num: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let (sign, num) = match num.strip_prefix('-') {
+        Some(n) => ("-", n),
+        None => ("", num),
+    };
+    let (int, frac) = match num.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (num, None),
+    };
+    let mut result = format!("{sign}{}", group(int, args)?);
+    if let Some(f) = frac {
+        result.push('.');
+        result.push_str(f);
+    }
+    Ok(result)
+}
+
+/// Format the value like C `printf`, supports the flags `-`, `+`,
+/// `0`, width, precision, and the conversions `d`, `f`, `e`, `x`, `X`
+/// and `s`. The value is used for every conversion in the format, and
+/// `%%` gives a literal `%`. e.g. `{val:printf(%05.2f)}`
 ///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(string_case("na", vec!["up"])?, "NA");
-///     assert_eq!(string_case("nA", vec!["down"])?, "na");
-///     assert_eq!(string_case("nA", vec!["proper"])?, "Na");
-///     assert_eq!(string_case("here, an apple", vec!["title"])?, "Here, an Apple");
+///     assert_eq!(printf("12.342323", vec!["%05.2f"])?, "12.34");
+///     assert_eq!(printf("2.5", vec!["%05.2f"])?, "02.50");
+///     assert_eq!(printf("-2.5", vec!["%07.2f"])?, "-002.50");
+///     assert_eq!(printf("2.5", vec!["%+.1f kg"])?, "+2.5 kg");
+///     assert_eq!(printf("42", vec!["%5d|%-5d|"])?, "   42|42   |");
+///     assert_eq!(printf("255", vec!["%x %04X"])?, "ff 00FF");
+///     assert_eq!(printf("1234.5", vec!["%.2e"])?, "1.23e+03");
+///     assert_eq!(printf("nata", vec!["%6.2s%%"])?, "    na%");
+///     assert!(printf("nata", vec!["%d"]).is_err());
 /// # Ok(())
 /// # }
-pub fn string_case(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "case";
+pub fn printf(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "printf";
     check_arguments_len(func_name, 1..=1, args.len())?;
     let format = args[0];
-    match format.to_lowercase().as_str() {
-        "up" => Ok(val.to_uppercase()),
-        "down" => Ok(val.to_lowercase()),
-        "title" => Ok(titlecase(val)),
-        "proper" => Ok({
-            let mut c = val.chars();
-            match c.next() {
-                None => String::new(),
-                Some(f) => {
-                    f.to_uppercase().collect::<String>() + c.as_str().to_lowercase().as_str()
-                }
+    let mut result = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        let mut left = false;
+        let mut zero = false;
+        let mut plus = false;
+        while let Some(f) = chars.next_if(|c| "-0+".contains(*c)) {
+            match f {
+                '-' => left = true,
+                '0' => zero = true,
+                _ => plus = true,
             }
-        }),
-        _ => Err(TransformerError::InvalidArgumentType(
+        }
+        let mut width = String::new();
+        while let Some(d) = chars.next_if(|c| c.is_ascii_digit()) {
+            width.push(d);
+        }
+        let width: usize = if width.is_empty() {
+            0
+        } else {
+            parse_format_width(func_name, &width)?
+        };
+        let precision: Option<usize> = if chars.next_if_eq(&'.').is_some() {
+            let mut p = String::new();
+            while let Some(d) = chars.next_if(|c| c.is_ascii_digit()) {
+                p.push(d);
+            }
+            Some(if p.is_empty() {
+                0
+            } else {
+                parse_format_width(func_name, &p)?
+            })
+        } else {
+            None
+        };
+        let conv = chars.next().ok_or(TransformerError::InvalidArgumentType(
             func_name,
             format.to_string(),
-            "{up;down;proper;title}",
-        )),
+            "format with conversion",
+        ))?;
+        let int = || parse_integer(func_name, val);
+        let float = || {
+            val.parse::<f64>()
+                .map_err(|_| TransformerError::InvalidValueType(func_name, "float"))
+        };
+        let (negative, body, numeric) = match conv {
+            '%' => {
+                result.push('%');
+                continue;
+            }
+            'd' => {
+                let v = int()?;
+                (v < 0, v.unsigned_abs().to_string(), true)
+            }
+            'x' => {
+                let v = int()?;
+                (v < 0, format!("{:x}", v.unsigned_abs()), true)
+            }
+            'X' => {
+                let v = int()?;
+                (v < 0, format!("{:X}", v.unsigned_abs()), true)
+            }
+            'f' => {
+                let v = float()?;
+                (
+                    v.is_sign_negative(),
+                    format!("{:.*}", precision.unwrap_or(6), v.abs()),
+                    true,
+                )
+            }
+            'e' => {
+                let v = float()?;
+                let e = format!("{:.*e}", precision.unwrap_or(6), v.abs());
+                let (m, exp) = e.split_once('e').unwrap_or((&e, "0"));
+                let (esign, exp) = match exp.strip_prefix('-') {
+                    Some(x) => ('-', x),
+                    None => ('+', exp),
+                };
+                (v.is_sign_negative(), format!("{m}e{esign}{exp:0>2}"), true)
+            }
+            's' => (
+                false,
+                match precision {
+                    Some(p) => val.chars().take(p).collect(),
+                    None => val.to_string(),
+                },
+                false,
+            ),
+            c => {
+                return Err(TransformerError::InvalidArgumentType(
+                    func_name,
+                    c.to_string(),
+                    "{d;f;e;x;X;s}",
+                ))
+            }
+        };
+        let sign = if negative {
+            "-"
+        } else if plus && numeric {
+            "+"
+        } else {
+            ""
+        };
+        let len = sign.len() + body.chars().count();
+        let pad = width.saturating_sub(len);
+        if left {
+            result.push_str(&format!("{sign}{body}{}", " ".repeat(pad)));
+        } else if zero && numeric {
+            result.push_str(&format!("{sign}{}{body}", "0".repeat(pad)));
+        } else {
+            result.push_str(&format!("{}{sign}{body}", " ".repeat(pad)));
+        }
     }
+    Ok(result)
 }
 
-lazy_static! {
-    static ref CALC_NUMBERS: Regex = Regex::new("[0-9.]+").unwrap();
+thread_local! {
+    /// Keys already used by [`once`] in the current render
+    static ONCE_SEEN: RefCell<Option<HashSet<String>>> = const { RefCell::new(None) };
+}
+
+/// Scope of a single render for [`once`], the keys are forgotten when
+/// the outermost scope is dropped, the nested renders (like the
+/// `render` transformer) share the keys with it.
+pub(crate) struct OnceScope {
+    outermost: bool,
+}
+
+impl OnceScope {
+    pub(crate) fn new() -> Self {
+        ONCE_SEEN.with(|seen| {
+            let mut seen = seen.borrow_mut();
+            let outermost = seen.is_none();
+            if outermost {
+                *seen = Some(HashSet::new());
+            }
+            Self { outermost }
+        })
+    }
 }
 
-/// Airthmatic calculations, the value needs to be float. e.g. `{val:calc(+1)}` will add 1 to the value. The order of calculation is left to right.
+impl Drop for OnceScope {
+    fn drop(&mut self) {
+        if self.outermost {
+            ONCE_SEEN.with(|seen| *seen.borrow_mut() = None);
+        }
+    }
+}
+
+/// Give the value only the first time it's used while rendering a
+/// template, it's empty after that. The value is the key to check if
+/// it's been used unless a key is given as the argument. Useful for
+/// headers that should only be shown once like `{header:once()}`.
+/// Outside of a render the value is always given.
 ///
 /// ```rust
 /// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use string_template_plus::{Render, RenderOptions, Template};
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(calc("1.24", vec!["+1"])?, "2.24");
-///     assert_eq!(calc("1", vec!["+1*2^2"])?, "16");
-///     assert_eq!(calc("1.24", vec!["+1", "-1"])?, "2.24,0.24");
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("h".into(), "Fruits: ".into());
+///     let options = RenderOptions {
+///         variables: vars,
+///         ..Default::default()
+///     };
+///     let templ = Template::parse_template("{h:once()}apple {h:once()}mango {h:once(x)}")?;
+///     assert_eq!(templ.render(&options)?, "Fruits: apple mango Fruits: ");
+///     assert_eq!(templ.render(&options)?, "Fruits: apple mango Fruits: ");
+///     assert_eq!(once("hi", vec![""])?, "hi");
+///     assert_eq!(once("hi", vec![""])?, "hi");
 /// # Ok(())
 /// # }
-pub fn calc(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "calc";
-    check_arguments_len(func_name, 1.., args.len())?;
-
-    let val: f64 = val
-        .parse()
-        .map_err(|_| TransformerError::InvalidValueType(func_name, "float"))?;
-    let mut results: Vec<String> = Vec::new();
-    for expr in args {
-        let mut last_match = 0usize;
-        let mut result = val;
-        for cap in CALC_NUMBERS.captures_iter(expr) {
-            let m = cap.get(0).unwrap();
-            let curr_val = m.as_str().parse().map_err(|_| {
-                TransformerError::InvalidArgumentType(func_name, m.as_str().to_string(), "float")
-            })?;
-            if m.start() == 0 {
-                result = curr_val;
-            } else {
-                match &expr[last_match..m.start()] {
-                    "+" => result += curr_val,
-                    "-" => result -= curr_val,
-                    "/" => result /= curr_val,
-                    "*" => result *= curr_val,
-                    "^" => result = result.powf(curr_val),
-                    s => {
-                        return Err(TransformerError::InvalidArgumentType(
-                            func_name,
-                            s.to_string(),
-                            "{+,-,*,/,^}",
-                        ))
-                    }
-                };
-            }
-            last_match = m.end();
-        }
-        results.push(result.to_string());
-    }
-    Ok(results.join(","))
+/// ```
+pub fn once(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "once";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let key = match args.first() {
+        Some(k) if !k.is_empty() => k,
+        _ => val,
+    };
+    let first = ONCE_SEEN.with(|seen| match seen.borrow_mut().as_mut() {
+        Some(seen) => seen.insert(key.to_string()),
+        None => true,
+    });
+    Ok(if first {
+        val.to_string()
+    } else {
+        String::new()
+    })
 }
 
-/// Count the number of occurances of a pattern in the string. You can chain it with [`calc`] to get the number of word like: `{val:count( ):calc(+1)}`
+/// Progress bar of the given width for the percentage value (`0` to
+/// `100`), the values outside of it are clamped. The characters for
+/// the filled and empty parts (default `#` and space) can be given
+/// as well. e.g. `{pct:bar(20)}` or `{pct:bar(10,=,-)}`
 ///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(count("nata", vec!["a"])?, "2");
-///     assert_eq!(count("nata", vec!["a", "t"])?, "2,1");
-///     assert_eq!(count("nata", vec![" "])?, "0");
-///     assert_eq!(count("hi there fellow", vec![" "])?, "2");
+///     assert_eq!(bar("0", vec!["10"])?, "[          ]");
+///     assert_eq!(bar("50", vec!["10"])?, "[#####     ]");
+///     assert_eq!(bar("100", vec!["10"])?, "[##########]");
+///     assert_eq!(bar("45", vec!["20"])?, "[#########           ]");
+///     assert_eq!(bar("150", vec!["4", "=", "-"])?, "[====]");
+///     assert_eq!(bar("-5", vec!["4", "=", "-"])?, "[----]");
+///     assert!(bar("half", vec!["10"]).is_err());
+///     assert!(bar("50", vec!["10", "=="]).is_err());
 /// # Ok(())
 /// # }
-pub fn count(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "count";
-    check_arguments_len(func_name, 1.., args.len())?;
-    let counts: Vec<String> = args
-        .iter()
-        .map(|sep| val.matches(sep).count().to_string())
-        .collect();
-    Ok(counts.join(","))
+/// ```
+pub fn bar(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "bar";
+    check_arguments_len(func_name, 1..=3, args.len())?;
+    let width = parse_format_width(func_name, args[0])?;
+    let char_arg = |i: usize, default: char| {
+        let arg = args.get(i).copied().unwrap_or_default();
+        let mut chars = arg.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => Ok(default),
+            (Some(c), None) => Ok(c),
+            _ => Err(TransformerError::InvalidArgumentType(
+                func_name,
+                arg.to_string(),
+                "char",
+            )),
+        }
+    };
+    let fill = char_arg(1, '#')?;
+    let empty = char_arg(2, ' ')?;
+    let pct: f64 = val
+        .trim()
+        .parse()
+        .ok()
+        .filter(|n: &f64| !n.is_nan())
+        .ok_or(TransformerError::InvalidValueType(func_name, "float"))?;
+    let filled = (pct.clamp(0.0, 100.0) / 100.0 * width as f64).round() as usize;
+    Ok(format!(
+        "[{}{}]",
+        fill.to_string().repeat(filled),
+        empty.to_string().repeat(width - filled)
+    ))
 }
 
-/// Replace text in the string, by another text
+/// Debug information about the value: the quoted value with escapes,
+/// the number of characters and whether it's a number. It's meant to
+/// find out why a template misbehaves, not for the final output, as
+/// the format might change. e.g. `{x:dbg()}`
 ///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(replace("nata", vec!["a", "o"])?, "noto");
-///     assert_eq!(replace("hi there fellow", vec![" ", "-"])?, "hi-there-fellow");
+///     assert_eq!(dbg("12.3", vec![])?, r#""12.3" (len=4, numeric)"#);
+///     assert_eq!(dbg("hi\n", vec![])?, r#""hi\n" (len=3, text)"#);
+///     assert_eq!(dbg("", vec![])?, r#""" (len=0, text)"#);
 /// # Ok(())
 /// # }
-pub fn replace(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "replace";
-    check_arguments_len(func_name, 2..=2, args.len())?;
-    Ok(val.replace(args[0], args[1]))
+/// ```
+pub fn dbg(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "dbg";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let kind = if val.trim().parse::<f64>().is_ok() {
+        "numeric"
+    } else {
+        "text"
+    };
+    Ok(format!("{:?} (len={}, {})", val, val.chars().count(), kind))
 }
 
-/// Split the text with given separator and then take the Nth group
-///
-/// N=0, will give the whole group separated by comma, but it might
-/// give unexpected results if there is already comma in string and
-/// you're splitting with something else
+/// Format the number with the given decimals (default 2) and the
+/// digits grouped by thousands, the negative numbers are shown in
+/// parentheses instead of the sign as done in accounting. e.g.
+/// `{x:accounting(2)}`
 ///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(take("nata", vec!["a", "2"])?, "t");
-///     assert_eq!(take("hi there fellow", vec![" ", "2"])?, "there");
-///     assert_eq!(take("hi there fellow", vec![" ", "2", "2"])?, "there fellow");
+///     assert_eq!(accounting("-1234.5", vec!["2"])?, "(1,234.50)");
+///     assert_eq!(accounting("1234.5", vec!["2"])?, "1,234.50");
+///     assert_eq!(accounting("-1234567", vec![""])?, "(1,234,567.00)");
+///     assert_eq!(accounting("12", vec!["0"])?, "12");
+///     assert_eq!(accounting("-0.001", vec!["2"])?, "0.00");
+///     assert!(accounting("twelve", vec!["2"]).is_err());
+///     assert!(accounting("12", vec!["two"]).is_err());
 /// # Ok(())
 /// # }
-pub fn take(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "take";
-    check_arguments_len(func_name, 2..=3, args.len())?;
+/// ```
+pub fn accounting(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "accounting";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let decimals = match args.first() {
+        Some(d) if !d.is_empty() => parse_format_width(func_name, d)?,
+        _ => 2,
+    };
+    let num: f64 = val
+        .trim()
+        .parse()
+        .ok()
+        .filter(|n: &f64| n.is_finite())
+        .ok_or(TransformerError::InvalidValueType(func_name, "float"))?;
+    let formatted = group_integer_part(&format!("{:.*}", decimals, num.abs()), vec![",", "3"])?;
+    // numbers that round to zero don't get the parentheses
+    if num < 0.0 && formatted.contains(|c: char| ('1'..='9').contains(&c)) {
+        Ok(format!("({formatted})"))
+    } else {
+        Ok(formatted)
+    }
+}
+
+/// Round the number to the given number of significant figures,
+/// unlike [`float_format`] which sets the number of decimals.
+/// e.g. `{x:sig(3)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(sig("0.0012345", vec!["3"])?, "0.00123");
+///     assert_eq!(sig("12345", vec!["3"])?, "12300");
+///     assert_eq!(sig("-12.345", vec!["3"])?, "-12.3");
+///     assert_eq!(sig("9.996", vec!["3"])?, "10.0");
+///     assert_eq!(sig("1.5", vec!["4"])?, "1.500");
+///     assert_eq!(sig("0", vec!["3"])?, "0");
+///     assert!(sig("nata", vec!["3"]).is_err());
+///     assert!(sig("1.5", vec!["0"]).is_err());
+///     assert!(sig("1.5", vec!["70000"]).is_err());
+///     assert!(sig("1e-300", vec!["2147483647"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn sig(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "sig";
+    check_arguments_len(func_name, 1..=1, args.len())?;
+    let figures: i32 = args[0].parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+        TransformerError::InvalidArgumentType(func_name, args[0].to_string(), "non-zero uint")
+    })?;
+    if figures > MAX_FORMAT_WIDTH as i32 {
+        return Err(TransformerError::ValueOutOfRange(
+            func_name,
+            args[0].to_string(),
+            "at most 65535",
+        ));
+    }
+    let num: f64 = val
+        .trim()
+        .parse()
+        .map_err(|_| TransformerError::InvalidValueType(func_name, "float"))?;
+    if num == 0.0 || !num.is_finite() {
+        return Ok(num.to_string());
+    }
+    let magnitude = |n: f64| n.abs().log10().floor() as i32;
+    let mut decimals = figures - 1 - magnitude(num);
+    // rounding can carry over to the next digit like 9.996 to 10.0
+    let scale = 10f64.powi(decimals);
+    if magnitude((num * scale).round() / scale) > magnitude(num) {
+        decimals -= 1;
+    }
+    if decimals > MAX_FORMAT_WIDTH as i32 {
+        return Err(TransformerError::ValueOutOfRange(
+            func_name,
+            args[0].to_string(),
+            "at most 65535 decimals",
+        ));
+    }
+    Ok(if decimals >= 0 {
+        format!("{:.*}", decimals as usize, num)
+    } else {
+        let scale = 10f64.powi(-decimals);
+        ((num / scale).round() * scale).to_string()
+    })
+}
+
+/// Right align the number within the given width, with the given
+/// number of decimals like `printf` does with `%8.2f`. The third
+/// argument can be `0` to pad with zeros after the sign instead of
+/// spaces. e.g. `{val:numalign(8,2)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(num_align("3.14159", vec!["8", "2"])?, "    3.14");
+///     assert_eq!(num_align("-3.14159", vec!["8", "2"])?, "   -3.14");
+///     assert_eq!(num_align("1234.5", vec!["8", "2"])?, " 1234.50");
+///     assert_eq!(num_align("-1234.5", vec!["8", "2"])?, "-1234.50");
+///     assert_eq!(num_align("-1234.5", vec!["4", "2"])?, "-1234.50");
+///     assert_eq!(num_align("-3.1", vec!["6", "1", "0"])?, "-003.1");
+///     assert_eq!(num_align("42", vec!["5"])?, "   42");
+///     assert!(num_align("nata", vec!["5"]).is_err());
+/// # Ok(())
+/// # }
+pub fn num_align(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "numalign";
+    check_arguments_len(func_name, 1..=3, args.len())?;
+    let width = parse_format_width(func_name, args[0])?;
+    let num: f64 = val
+        .trim()
+        .parse()
+        .map_err(|_| TransformerError::InvalidValueType(func_name, "float"))?;
+    let body = match args.get(1) {
+        Some(d) if !d.is_empty() => {
+            format!("{:.*}", parse_format_width(func_name, d)?, num.abs())
+        }
+        _ => num.abs().to_string(),
+    };
+    let sign = if num.is_sign_negative() { "-" } else { "" };
+    let pad = width.saturating_sub(sign.len() + body.len());
+    match args.get(2) {
+        None | Some(&"") | Some(&" ") => Ok(format!("{}{sign}{body}", " ".repeat(pad))),
+        Some(&"0") => Ok(format!("{sign}{}{body}", "0".repeat(pad))),
+        Some(f) => Err(TransformerError::InvalidArgumentType(
+            func_name,
+            f.to_string(),
+            "{0; }",
+        )),
+    }
+}
+
+/// Center the text within the given width using the fill character
+/// (space by default). When the padding is odd, the extra fill
+/// character goes on the right. Text longer than the width is left
+/// unchanged. e.g. `{title:center(20)}`, `{title:center(20,-)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(center("ab", vec!["6"])?, "  ab  ");
+///     assert_eq!(center("ab", vec!["7", "-"])?, "--ab---");
+///     assert_eq!(center("title", vec!["3"])?, "title");
+///     assert!(center("ab", vec!["6", "--"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn center(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "center";
+    check_arguments_len(func_name, 1..=2, args.len())?;
+    let width = parse_format_width(func_name, args[0])?;
+    let fill = match args.get(1) {
+        None | Some(&"") => ' ',
+        Some(f) => {
+            let mut chars = f.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => {
+                    return Err(TransformerError::InvalidArgumentType(
+                        func_name,
+                        f.to_string(),
+                        "char",
+                    ))
+                }
+            }
+        }
+    };
+    let pad = width.saturating_sub(val.chars().count());
+    let left = pad / 2;
+    Ok(format!(
+        "{}{val}{}",
+        fill.to_string().repeat(left),
+        fill.to_string().repeat(pad - left)
+    ))
+}
+
+/// Uppercase the text with the special rules of the locale
+fn locale_upper(val: &str, locale: &str) -> String {
+    match locale {
+        "tr" | "az" => val
+            .chars()
+            .map(|c| match c {
+                'i' => "İ".to_string(),
+                c => c.to_uppercase().collect(),
+            })
+            .collect(),
+        _ => val.to_uppercase(),
+    }
+}
+
+/// Lowercase the text with the special rules of the locale
+fn locale_lower(val: &str, locale: &str) -> String {
+    match locale {
+        "tr" | "az" => val
+            .chars()
+            .map(|c| match c {
+                'I' => "ı".to_string(),
+                'İ' => "i".to_string(),
+                c => c.to_lowercase().collect(),
+            })
+            .collect(),
+        _ => val.to_lowercase(),
+    }
+}
+
+/// Format the string. Supports `up`=> UPCASE, `down`=> downcase, `proper` => first character UPCASE all others downcase, `title` => title case according to [`titlecase::titlecase`]. e.g. `{var:case(up)}`.
+///
+/// The optional second argument is the locale for languages with
+/// special casing rules, Turkish (`tr`) and Azerbaijani (`az`) are
+/// supported for the dotted and dotless i, other locales use the
+/// default Unicode rules. The `title` case ignores the locale.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(string_case("na", vec!["up"])?, "NA");
+///     assert_eq!(string_case("nA", vec!["down"])?, "na");
+///     assert_eq!(string_case("nA", vec!["proper"])?, "Na");
+///     assert_eq!(string_case("here, an apple", vec!["title"])?, "Here, an Apple");
+///     assert_eq!(string_case("istanbul", vec!["up", "tr"])?, "İSTANBUL");
+///     assert_eq!(string_case("ISPARTA", vec!["down", "tr"])?, "ısparta");
+///     assert_eq!(string_case("izmir", vec!["proper", "tr"])?, "İzmir");
+///     assert_eq!(string_case("istanbul", vec!["up", "xx"])?, "ISTANBUL");
+/// # Ok(())
+/// # }
+pub fn string_case(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "case";
+    check_arguments_len(func_name, 1..=2, args.len())?;
+    let format = args[0];
+    let locale = args.get(1).map(|l| l.to_lowercase()).unwrap_or_default();
+    match format.to_lowercase().as_str() {
+        "up" => Ok(locale_upper(val, &locale)),
+        "down" => Ok(locale_lower(val, &locale)),
+        "title" => Ok(titlecase(val)),
+        "proper" => Ok({
+            let mut c = val.chars();
+            match c.next() {
+                None => String::new(),
+                Some(f) => {
+                    locale_upper(&f.to_string(), &locale) + &locale_lower(c.as_str(), &locale)
+                }
+            }
+        }),
+        _ => Err(TransformerError::InvalidArgumentType(
+            func_name,
+            format.to_string(),
+            "{up;down;proper;title}",
+        )),
+    }
+}
+
+lazy_static! {
+    static ref CALC_NUMBERS: Regex = Regex::new("[0-9.]+").unwrap();
+}
+
+/// Airthmatic calculations, the value needs to be float. e.g. `{val:calc(+1)}` will add 1 to the value. The order of calculation is left to right. Use [`math`] for the usual operator precedence.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(calc("1.24", vec!["+1"])?, "2.24");
+///     assert_eq!(calc("1", vec!["+1*2^2"])?, "16");
+///     assert_eq!(calc("1.24", vec!["+1", "-1"])?, "2.24,0.24");
+///     assert!(calc("1", vec!["++1"]).is_err());
+///     assert!(calc("1", vec!["*/2"]).is_err());
+///     assert!(calc("1", vec!["+1+"]).is_err());
+///     assert_eq!(calc("21", vec!["+1 "])?, "22");
+/// # Ok(())
+/// # }
+pub fn calc(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "calc";
+    check_arguments_len(func_name, 1.., args.len())?;
+
+    let val: f64 = val
+        .parse()
+        .map_err(|_| TransformerError::InvalidValueType(func_name, "float"))?;
+    let mut results: Vec<String> = Vec::new();
+    for expr in args {
+        let mut last_match = 0usize;
+        let mut result = val;
+        for cap in CALC_NUMBERS.captures_iter(expr) {
+            let m = cap.get(0).unwrap();
+            let curr_val = m.as_str().parse().map_err(|_| {
+                TransformerError::InvalidArgumentType(func_name, m.as_str().to_string(), "float")
+            })?;
+            if m.start() == 0 {
+                result = curr_val;
+            } else {
+                match &expr[last_match..m.start()] {
+                    "+" => result += curr_val,
+                    "-" => result -= curr_val,
+                    "/" => result /= curr_val,
+                    "*" => result *= curr_val,
+                    "^" => result = result.powf(curr_val),
+                    s if s.len() > 1 && s.chars().all(|c| "+-*/^".contains(c)) => {
+                        return Err(TransformerError::InvalidExpression(
+                            func_name,
+                            expr.to_string(),
+                            format!("adjacent operators {s} before {}", m.as_str()),
+                        ))
+                    }
+                    s => {
+                        return Err(TransformerError::InvalidArgumentType(
+                            func_name,
+                            s.to_string(),
+                            "{+,-,*,/,^}",
+                        ))
+                    }
+                };
+            }
+            last_match = m.end();
+        }
+        let rest = expr[last_match..].trim();
+        if !rest.is_empty() {
+            return Err(TransformerError::InvalidExpression(
+                func_name,
+                expr.to_string(),
+                format!("no number after {rest}"),
+            ));
+        }
+        results.push(result.to_string());
+    }
+    Ok(results.join(","))
+}
+
+/// Airthmatic calculations with the usual operator precedence, unlike
+/// [`calc`] which calculates from left to right. The expression
+/// starting with an operator uses the value as the first number, so
+/// `{val:math(+1*2)}` is `val + (1 * 2)` while `{val:calc(+1*2)}` is
+/// `(val + 1) * 2`. The power (`^`) is done first, then the
+/// multiplication, division and remainder (`%`), and then the
+/// addition and subtraction. Parentheses can be used to group the
+/// calculations, the groups at the start can also start with an
+/// operator like `{val:math((+1)*2)}` which is `(val + 1) * 2`.
+///
+/// The functions `sin`, `cos`, `tan`, `sqrt`, `log` (base 10), `ln`,
+/// `exp` and `abs` can be used with or without the parentheses like
+/// `sin(2)` or `sin 2`, and use the value when there's nothing after
+/// them, so `{val:math(sqrt)}` is the square root of the value. The
+/// value can also be used anywhere as `self` like
+/// `{val:math(2*sin(self))}`. A group right after a number is
+/// multiplied with it, so `{val:math(* 2 (sin self))}` is
+/// `val * 2 * sin(val)`. The angles for the trigonometric functions
+/// are in radians.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(math("1", vec!["+1*2"])?, "3");
+///     assert_eq!(calc("1", vec!["+1*2"])?, "4");
+///     assert_eq!(math("1", vec!["+2*3^2"])?, "19");
+///     assert_eq!(calc("1", vec!["+2*3^2"])?, "81");
+///     assert_eq!(math("10", vec!["-4/2", "*-1"])?, "8,-10");
+///     assert_eq!(math("1", vec!["2^3^2"])?, "512");
+///     assert_eq!(math("1", vec!["(+1)*2"])?, "4");
+///     assert_eq!(math("1", vec!["((+1)*(2+1))^2"])?, "36");
+///     assert_eq!(math("3", vec!["*(2-(1+4))"])?, "-9");
+///     assert_eq!(math("17", vec!["%5+1"])?, "3");
+///     assert_eq!(math("2", vec!["*(-1)"])?, "-2");
+///     assert_eq!(math("16", vec!["sqrt", "sqrt+1", "sqrt 9"])?, "4,5,3");
+///     assert_eq!(math("0", vec!["sin", "cos(self)"])?, "0,1");
+///     assert_eq!(math("1.5707963267948966", vec!["2*sin(self)"])?, "2");
+///     assert_eq!(math("1.5707963267948966", vec!["* 2 (sin self)"])?, "3.141592653589793");
+///     assert_eq!(math("3", vec!["2 (1+1)^2"])?, "8");
+///     assert_eq!(math("100", vec!["log", "(sqrt self)^2", "ln(exp 2)"])?, "2,100,2");
+///     assert!(math("1", vec!["foo(1)"]).is_err());
+///     assert!(math("1", vec!["(+1*2"]).is_err());
+///     assert!(math("1", vec!["+1)*2"]).is_err());
+///     assert!(math("1", vec!["+1*"]).is_err());
+///     assert!(math("1", vec!["+1 2"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn math(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "math";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let val: f64 = val
+        .trim()
+        .parse()
+        .map_err(|_| TransformerError::InvalidValueType(func_name, "float"))?;
+    args.iter()
+        .map(|expr| {
+            MathParser::new(func_name, expr, val)
+                .and_then(|mut p| p.parse())
+                .map(|r| r.to_string())
+        })
+        .collect::<Result<Vec<String>, TransformerError>>()
+        .map(|r| r.join(","))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MathToken<'a> {
+    Num(f64),
+    Op(char),
+    /// function names and `self`
+    Name(&'a str),
+}
+
+impl fmt::Display for MathToken<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Num(n) => write!(f, "{n}"),
+            Self::Op(c) => write!(f, "{c}"),
+            Self::Name(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+/// Recursive descent parser for the expressions in [`math`]
+struct MathParser<'a> {
+    func_name: &'static str,
+    expr: &'a str,
+    tokens: Vec<MathToken<'a>>,
+    pos: usize,
+    val: f64,
+}
+
+impl<'a> MathParser<'a> {
+    fn new(func_name: &'static str, expr: &'a str, val: f64) -> Result<Self, TransformerError> {
+        let mut tokens = Vec::new();
+        let mut chars = expr.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c.is_whitespace() => (),
+                '+' | '-' | '*' | '/' | '%' | '^' | '(' | ')' => tokens.push(MathToken::Op(c)),
+                '0'..='9' | '.' => {
+                    let mut end = i + 1;
+                    while let Some((j, '0'..='9' | '.')) = chars.peek() {
+                        end = j + 1;
+                        chars.next();
+                    }
+                    let num = expr[i..end].parse().map_err(|_| {
+                        TransformerError::InvalidArgumentType(
+                            func_name,
+                            expr[i..end].to_string(),
+                            "float",
+                        )
+                    })?;
+                    tokens.push(MathToken::Num(num));
+                }
+                'a'..='z' => {
+                    let mut end = i + 1;
+                    while let Some((j, 'a'..='z')) = chars.peek() {
+                        end = j + 1;
+                        chars.next();
+                    }
+                    tokens.push(MathToken::Name(&expr[i..end]));
+                }
+                c => {
+                    return Err(TransformerError::InvalidExpression(
+                        func_name,
+                        expr.to_string(),
+                        format!("unknown character {c}"),
+                    ))
+                }
+            }
+        }
+        // the value is the first number when it starts with an
+        // operator, which can be inside the starting parentheses
+        let start = tokens
+            .iter()
+            .position(|t| *t != MathToken::Op('('))
+            .unwrap_or(tokens.len());
+        if let Some(MathToken::Op(c)) = tokens.get(start) {
+            if *c != ')' {
+                tokens.insert(start, MathToken::Num(val));
+            }
+        }
+        Ok(Self {
+            func_name,
+            expr,
+            tokens,
+            pos: 0,
+            val,
+        })
+    }
+
+    fn error(&self, msg: String) -> TransformerError {
+        TransformerError::InvalidExpression(self.func_name, self.expr.to_string(), msg)
+    }
+
+    fn next_op(&mut self, ops: &str) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(MathToken::Op(c)) if ops.contains(*c) => {
+                self.pos += 1;
+                Some(*c)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse(&mut self) -> Result<f64, TransformerError> {
+        let result = self.sum()?;
+        match self.tokens.get(self.pos) {
+            None => Ok(result),
+            Some(MathToken::Op(')')) => Err(self.error("unbalanced parentheses, extra )".into())),
+            Some(t) => Err(self.error(format!("unexpected {t} after the expression"))),
+        }
+    }
+
+    /// addition and subtraction
+    fn sum(&mut self) -> Result<f64, TransformerError> {
+        let mut result = self.product()?;
+        while let Some(op) = self.next_op("+-") {
+            let rhs = self.product()?;
+            match op {
+                '+' => result += rhs,
+                _ => result -= rhs,
+            }
+        }
+        Ok(result)
+    }
+
+    /// multiplication, division and remainder, a group right after a
+    /// number like `2 (1+1)` is also multiplied
+    fn product(&mut self) -> Result<f64, TransformerError> {
+        let mut result = self.unary()?;
+        loop {
+            if let Some(op) = self.next_op("*/%") {
+                let rhs = self.unary()?;
+                match op {
+                    '*' => result *= rhs,
+                    '/' => result /= rhs,
+                    _ => result %= rhs,
+                }
+            } else if self.group_after_number() {
+                result *= self.unary()?;
+            } else {
+                return Ok(result);
+            }
+        }
+    }
+
+    fn group_after_number(&self) -> bool {
+        self.pos > 0
+            && matches!(self.tokens[self.pos - 1], MathToken::Num(_))
+            && self.tokens.get(self.pos) == Some(&MathToken::Op('('))
+    }
+
+    /// negative numbers, `-2^2` is `-(2^2)`
+    fn unary(&mut self) -> Result<f64, TransformerError> {
+        if self.next_op("-").is_some() {
+            Ok(-self.unary()?)
+        } else {
+            self.power()
+        }
+    }
+
+    /// power, calculated from right to left
+    fn power(&mut self) -> Result<f64, TransformerError> {
+        let base = self.primary()?;
+        if self.next_op("^").is_some() {
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<f64, TransformerError> {
+        if self.next_op("(").is_some() {
+            let result = self.sum()?;
+            return match self.next_op(")") {
+                Some(_) => Ok(result),
+                None => Err(self.error("unbalanced parentheses, ( not closed".into())),
+            };
+        }
+        match self.tokens.get(self.pos).copied() {
+            Some(MathToken::Num(n)) => {
+                self.pos += 1;
+                Ok(n)
+            }
+            Some(MathToken::Name("self")) => {
+                self.pos += 1;
+                Ok(self.val)
+            }
+            Some(MathToken::Name(name)) => {
+                self.pos += 1;
+                let func: fn(f64) -> f64 = match name {
+                    "sin" => f64::sin,
+                    "cos" => f64::cos,
+                    "tan" => f64::tan,
+                    "sqrt" => f64::sqrt,
+                    "log" => f64::log10,
+                    "ln" => f64::ln,
+                    "exp" => f64::exp,
+                    "abs" => f64::abs,
+                    _ => return Err(self.error(format!("unknown function {name}"))),
+                };
+                // the value is used when there's nothing to apply it to
+                let arg = match self.tokens.get(self.pos) {
+                    Some(MathToken::Num(_) | MathToken::Name(_) | MathToken::Op('(')) => {
+                        self.primary()?
+                    }
+                    _ => self.val,
+                };
+                Ok(func(arg))
+            }
+            Some(t) => Err(self.error(format!("expected a number, found {t}"))),
+            None => Err(self.error("expected a number at the end".to_string())),
+        }
+    }
+}
+
+/// Count the number of occurances of a pattern in the string. The
+/// pattern is matched literally, so `{val:count( ):calc(+1)}` only
+/// gives the number of words when they are separated by single
+/// spaces, use [`word_count`] (`{val:wc(words)}`) to count words
+/// instead. The pattern can not be empty.
+///
+/// If the pattern has a newline, `\r\n` and `\r` in the text are
+/// counted as newline as well.
+///
+/// The occurrences are non-overlapping by default, so `aa` is
+/// counted once in `aaa`. If the last of multiple arguments is
+/// `overlap`, overlapping occurrences are counted instead, so `aa`
+/// is counted twice in `aaa`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(count("nata", vec!["a"])?, "2");
+///     assert_eq!(count("nata", vec!["a", "t"])?, "2,1");
+///     assert_eq!(count("nata", vec![" "])?, "0");
+///     assert_eq!(count("hi there fellow", vec![" "])?, "2");
+///     assert_eq!(count("a  b ", vec![" "])?, "3");
+///     assert!(count("nata", vec![""]).is_err());
+///     assert_eq!(count("a\r\nb\rc\n", vec!["\n"])?, "3");
+///     assert_eq!(count("aaa", vec!["aa"])?, "1");
+///     assert_eq!(count("aaa", vec!["aa", "overlap"])?, "2");
+///     assert_eq!(count("ATATAT", vec!["ATA", "T", "overlap"])?, "2,3");
+///     assert_eq!(count("overlap", vec!["overlap"])?, "1");
+/// # Ok(())
+/// # }
+pub fn count(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "count";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let (patterns, overlap) = match args.as_slice() {
+        [patterns @ .., "overlap"] if !patterns.is_empty() => (patterns, true),
+        patterns => (patterns, false),
+    };
+    let counts: Vec<String> = patterns
+        .iter()
+        .map(|sep| {
+            if sep.is_empty() {
+                Err(TransformerError::InvalidArgumentType(
+                    func_name,
+                    sep.to_string(),
+                    "non-empty string",
+                ))
+            } else {
+                let text = normalize_newlines(val, sep);
+                Ok(if overlap {
+                    // char boundaries as the sliding window start
+                    text.char_indices()
+                        .filter(|(i, _)| text[*i..].starts_with(sep))
+                        .count()
+                } else {
+                    text.matches(sep).count()
+                }
+                .to_string())
+            }
+        })
+        .collect::<Result<Vec<String>, TransformerError>>()?;
+    Ok(counts.join(","))
+}
+
+/// Count the number of words, lines or characters in the string. Words are separated by any amount of whitespace.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(word_count("hi there fellow", vec!["words"])?, "3");
+///     assert_eq!(word_count("  hi   there  fellow ", vec!["words"])?, "3");
+///     assert_eq!(word_count("a  b ", vec!["words"])?, "2");
+///     assert_eq!(word_count("hi\nthere\nfellow", vec!["lines"])?, "3");
+///     assert_eq!(word_count("hi\r\nthere\rfellow", vec!["lines"])?, "3");
+///     assert_eq!(word_count("nata", vec!["chars"])?, "4");
+///     assert_eq!(word_count("hi there", vec!["words", "chars"])?, "2,8");
+/// # Ok(())
+/// # }
+pub fn word_count(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "wc";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let counts: Vec<String> = args
+        .iter()
+        .map(|metric| match *metric {
+            "words" => Ok(val.split_whitespace().count().to_string()),
+            "lines" => Ok(normalize_newlines(val, "\n").lines().count().to_string()),
+            "chars" => Ok(val.chars().count().to_string()),
+            m => Err(TransformerError::InvalidArgumentType(
+                func_name,
+                m.to_string(),
+                "{words;lines;chars}",
+            )),
+        })
+        .collect::<Result<Vec<String>, TransformerError>>()?;
+    Ok(counts.join(","))
+}
+
+/// Replace text in the string, by another text. The optional third
+/// argument limits the number of replacements from the start.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(replace("nata", vec!["a", "o"])?, "noto");
+///     assert_eq!(replace("hi there fellow", vec![" ", "-"])?, "hi-there-fellow");
+///     assert_eq!(replace("nata", vec!["a", "o", "1"])?, "nota");
+///     assert_eq!(replace("hi there fellow", vec![" ", "-", "5"])?, "hi-there-fellow");
+/// # Ok(())
+/// # }
+pub fn replace(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "replace";
+    check_arguments_len(func_name, 2..=3, args.len())?;
+    if args.len() == 3 {
+        let n: usize = args[2].parse().map_err(|_| {
+            TransformerError::InvalidArgumentType(func_name, args[2].to_string(), "uint")
+        })?;
+        Ok(val.replacen(args[0], args[1], n))
+    } else {
+        Ok(val.replace(args[0], args[1]))
+    }
+}
+
+/// Take the Nth column of the text like `awk '{print $N}'`, the
+/// columns are separated by any amount of whitespace, ignoring the
+/// leading and trailing whitespace. The optional second argument is
+/// the delimiter to use instead, with which the empty columns are
+/// kept like `awk -F`. N=0 gives the whole text, and the columns that
+/// don't exist are empty. e.g. `{line:col(2)}`, `{line:col(2,:)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(col("  root   1234  0.0 bash", vec!["2"])?, "1234");
+///     assert_eq!(col("a\tb  c", vec!["3"])?, "c");
+///     assert_eq!(col("root:x:0:0", vec!["3", ":"])?, "0");
+///     assert_eq!(col("a::c", vec!["2", ":"])?, "");
+///     assert_eq!(col("a b", vec!["5"])?, "");
+///     assert_eq!(col("a b", vec!["0"])?, "a b");
+///     assert!(col("a b", vec!["x"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn col(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "col";
+    check_arguments_len(func_name, 1..=2, args.len())?;
+    let n: usize = args[0].parse().map_err(|_| {
+        TransformerError::InvalidArgumentType(func_name, args[0].to_string(), "uint")
+    })?;
+    if n == 0 {
+        return Ok(val.to_string());
+    }
+    let column = match args.get(1) {
+        None | Some(&"") => val.split_whitespace().nth(n - 1),
+        Some(delim) => val.split(delim).nth(n - 1),
+    };
+    Ok(column.unwrap_or("").to_string())
+}
+
+/// Split the text with given separator and then take the Nth group
+///
+/// N=0, will give the whole group separated by comma, but it might
+/// give unexpected results if there is already comma in string and
+/// you're splitting with something else
+///
+/// If the separator has a newline, `\r\n` and `\r` in the text are
+/// treated as newline as well.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(take("nata", vec!["a", "2"])?, "t");
+///     assert_eq!(take("hi there fellow", vec![" ", "2"])?, "there");
+///     assert_eq!(take("hi there fellow", vec![" ", "2", "2"])?, "there fellow");
+///     assert_eq!(take("hi\r\nthere\r\nfellow", vec!["\n", "2"])?, "there");
+///     assert_eq!(take("hi there fellow", vec![" ", "0"])?, "hi,there,fellow");
+///     assert_eq!(take("hi there fellow", vec![" ", "0", "2"])?, "hi,there fellow");
+///     assert_eq!(take("hi there", vec![" ", "18446744073709551615"])?, "");
+///     assert!(take("hi there", vec![" ", "18446744073709551616"]).is_err());
+/// # Ok(())
+/// # }
+pub fn take(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "take";
+    check_arguments_len(func_name, 2..=3, args.len())?;
+    let val = normalize_newlines(val, args[0]);
     let n: usize = args[1].parse().map_err(|_| {
         TransformerError::InvalidArgumentType(func_name, args[1].to_string(), "uint")
     })?;
-    let spl = if args.len() == 2 {
-        val.split(args[0]).nth(n - 1)
+    let mut groups: Vec<&str> = if args.len() == 2 {
+        val.split(args[0]).collect()
+    } else {
+        val.splitn(
+            args[2].parse().map_err(|_| {
+                TransformerError::InvalidArgumentType(func_name, args[2].to_string(), "uint")
+            })?,
+            args[0],
+        )
+        .collect()
+    };
+    if n == 0 {
+        return Ok(groups.join(","));
+    }
+    Ok(if n <= groups.len() {
+        groups.swap_remove(n - 1).to_string()
+    } else {
+        String::new()
+    })
+}
+
+/// Remove the accents and other diacritical marks from the text, like
+/// `café` to `cafe`. The text is decomposed and the combining marks
+/// from the Unicode block `U+0300..U+036F` are removed, so the other
+/// scripts (including the ones that use combining marks for vowels,
+/// like Devanagari) are kept as they are. Letters like `ø` or `ß`
+/// that aren't composed with marks are also kept unchanged.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(deaccent("café", vec![""])?, "cafe");
+///     assert_eq!(deaccent("naïve", vec![""])?, "naive");
+///     assert_eq!(deaccent("Ångström Crème Brûlée", vec![""])?, "Angstrom Creme Brulee");
+///     assert_eq!(deaccent("Øre straße", vec![""])?, "Øre straße");
+///     assert_eq!(deaccent("नमस्ते 한국", vec![""])?, "नमस्ते 한국");
+/// # Ok(())
+/// # }
+/// ```
+pub fn deaccent(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "deaccent";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    Ok(val
+        .nfd()
+        .filter(|c| !('\u{0300}'..='\u{036f}').contains(c))
+        .nfc()
+        .collect())
+}
+
+/// Append the Luhn check digit to the number, or with `check` as the
+/// argument, give `true` or `false` based on whether the number ends
+/// with a valid check digit. e.g. `{card:luhn()}`, `{card:luhn(check)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(luhn("7992739871", vec![""])?, "79927398713");
+///     assert_eq!(luhn("79927398713", vec!["check"])?, "true");
+///     assert_eq!(luhn("79927398710", vec!["check"])?, "false");
+///     assert!(luhn("7992-7398", vec![""]).is_err());
+///     assert!(luhn("", vec!["check"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn luhn(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "luhn";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let digits: Vec<u32> = val
+        .chars()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()
+        .filter(|d| !d.is_empty())
+        .ok_or(TransformerError::InvalidValueType(func_name, "digits"))?;
+    // sum with every second digit doubled, starting from the right
+    let luhn_sum = |digits: &[u32]| -> u32 {
+        digits
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, d)| match (i % 2, d * 2) {
+                (0, d2) if d2 > 9 => d2 - 9,
+                (0, d2) => d2,
+                _ => *d,
+            })
+            .sum()
+    };
+    match args.first() {
+        None | Some(&"") => Ok(format!("{val}{}", (10 - luhn_sum(&digits) % 10) % 10)),
+        Some(&"check") => {
+            let (check, payload) = digits.split_last().unwrap();
+            Ok(((10 - luhn_sum(payload) % 10) % 10 == *check).to_string())
+        }
+        Some(a) => Err(TransformerError::InvalidArgumentType(
+            func_name,
+            a.to_string(),
+            "check",
+        )),
+    }
+}
+
+/// Hex dump of the UTF-8 bytes of the text like `hexdump -C`, each
+/// line has the offset, the bytes in hex, and the printable ASCII
+/// characters (`.` for the others). The argument is the number of
+/// bytes per line (default 16). e.g. `{data:hexdump()}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(
+///         hexdump("hi\n", vec![""])?,
+///         "00000000  68 69 0a                                         |hi.|"
+///     );
+///     assert_eq!(
+///         hexdump("héllo", vec!["4"])?,
+///         "00000000  68 c3 a9 6c  |h..l|\n00000004  6c 6f        |lo|"
+///     );
+///     assert_eq!(hexdump("", vec![""])?, "");
+///     assert!(hexdump("hi", vec!["0"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn hexdump(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "hexdump";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let width = match args.first() {
+        None | Some(&"") => 16,
+        Some(w) => parse_format_width(func_name, w)?,
+    };
+    if width == 0 {
+        return Err(TransformerError::InvalidArgumentType(
+            func_name,
+            "0".to_string(),
+            "non-zero uint",
+        ));
+    }
+    Ok(val
+        .as_bytes()
+        .chunks(width)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|b| match b {
+                    0x20..=0x7e => *b as char,
+                    _ => '.',
+                })
+                .collect();
+            format!(
+                "{:08x}  {:<3$}  |{}|",
+                i * width,
+                hex.join(" "),
+                ascii,
+                width * 3 - 1
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+/// Pick one of the arguments using the value as the index (starting
+/// from 0), wrapping around when the index is larger than the number
+/// of arguments. Negative indices count from the end. It can be used
+/// with [`bucket`] to pick from the values based on some text. e.g.
+/// `{i:pick(red,green,blue)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(pick("0", vec!["a", "b", "c"])?, "a");
+///     assert_eq!(pick("2", vec!["a", "b", "c"])?, "c");
+///     assert_eq!(pick("4", vec!["a", "b", "c"])?, "b");
+///     assert_eq!(pick("-1", vec!["a", "b", "c"])?, "c");
+///     assert!(pick("1.5", vec!["a", "b", "c"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn pick(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "pick";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let index = parse_integer(func_name, val.trim())?;
+    Ok(args[index.rem_euclid(args.len() as i128) as usize].to_string())
+}
+
+/// Compare the numeric value with a number and give the first branch
+/// if the comparison is true, else the second one. Operators
+/// supported are `>`, `<`, `>=`, `<=`, `==` and `!=`. e.g.
+/// `{x:cmp(>,10,high,low)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(cmp("12", vec![">", "10", "high", "low"])?, "high");
+///     assert_eq!(cmp("8", vec!["<", "10", "low", "high"])?, "low");
+///     assert_eq!(cmp("10", vec![">=", "10", "yes", "no"])?, "yes");
+///     assert_eq!(cmp("10.5", vec!["<=", "10", "yes", "no"])?, "no");
+///     assert_eq!(cmp("10.0", vec!["==", "10", "ten", "other"])?, "ten");
+///     assert_eq!(cmp("3", vec!["!=", "3", "yes", "no"])?, "no");
+///     assert!(cmp("3", vec!["=", "3", "yes", "no"]).is_err());
+///     assert!(cmp("three", vec!["==", "3", "yes", "no"]).is_err());
+///     assert!(cmp("3", vec!["==", "3", "yes"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn cmp(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "cmp";
+    check_arguments_len(func_name, 4..=4, args.len())?;
+    let val = val
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| TransformerError::InvalidValueType(func_name, "float"))?;
+    let other = args[1].trim().parse::<f64>().map_err(|_| {
+        TransformerError::InvalidArgumentType(func_name, args[1].to_string(), "float")
+    })?;
+    let res = match args[0].trim() {
+        ">" => val > other,
+        "<" => val < other,
+        ">=" => val >= other,
+        "<=" => val <= other,
+        "==" => val == other,
+        "!=" => val != other,
+        op => {
+            return Err(TransformerError::InvalidArgumentType(
+                func_name,
+                op.to_string(),
+                "{>,<,>=,<=,==,!=}",
+            ))
+        }
+    };
+    Ok(if res { args[2] } else { args[3] }.to_string())
+}
+
+/// Stable bucket index from `0` to `N-1` for the text, useful to
+/// assign colors or shards based on a value. The index is the 64 bit
+/// FNV-1a hash of the UTF-8 bytes modulo `N`, so it's the same across
+/// runs and platforms. e.g. `{user:bucket(8)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(bucket("alice", vec!["8"])?, "7");
+///     assert_eq!(bucket("alice", vec!["8"])?, bucket("alice", vec!["8"])?);
+///     assert_eq!(bucket("", vec!["10"])?, "7");
+///     assert!(bucket("alice", vec!["0"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn bucket(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "bucket";
+    check_arguments_len(func_name, 1..=1, args.len())?;
+    let n: u64 = args[0].parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+        TransformerError::InvalidArgumentType(func_name, args[0].to_string(), "non-zero uint")
+    })?;
+    let hash = val.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    Ok((hash % n).to_string())
+}
+
+/// Join the lines of the text into a single line with the given
+/// separator (default space), each line is trimmed and the empty
+/// lines are skipped. Tabs are also replaced with the separator if
+/// the second argument is `tab`. e.g. `{stack:oneline( | )}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(oneline("error:\n  at main\n\n  at run\n", vec![""])?, "error: at main at run");
+///     assert_eq!(oneline("a\r\nb", vec![" | "])?, "a | b");
+///     assert_eq!(oneline("a\tb\nc", vec![","])?, "a\tb,c");
+///     assert_eq!(oneline("a\tb\nc", vec![",", "tab"])?, "a,b,c");
+///     assert!(oneline("a\tb", vec![",", "tabs"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn oneline(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "oneline";
+    check_arguments_len(func_name, ..=2, args.len())?;
+    let sep = match args.first() {
+        None | Some(&"") => " ",
+        Some(s) => s,
+    };
+    let tabs = match args.get(1) {
+        None | Some(&"") => false,
+        Some(&"tab") => true,
+        Some(t) => {
+            return Err(TransformerError::InvalidArgumentType(
+                func_name,
+                t.to_string(),
+                "tab",
+            ))
+        }
+    };
+    Ok(val
+        .lines()
+        .flat_map(|l| l.split(if tabs { '\t' } else { '\n' }))
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<&str>>()
+        .join(sep))
+}
+
+/// Trim the given string with given patterns one after another
+///
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(trim("nata", vec!["a"])?, "nat");
+///     assert_eq!(trim("  \tnata\t  ", vec![])?, "nata");
+///     assert_eq!(trim("hi there! ", vec![" ", "!"])?, "hi there");
+///     assert_eq!(trim("hi there! ", vec![" !", "ih"])?, " there");
+/// # Ok(())
+/// # }
+pub fn trim(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "trim";
+    check_arguments_len(func_name, .., args.len())?;
+    if args.is_empty() {
+        return Ok(val.trim().to_string());
+    }
+    let mut val = val;
+    for arg in args {
+        val = val.trim_matches(|c| arg.contains(c))
+    }
+
+    Ok(val.to_string())
+}
+
+/// Trim the given string with given patterns one after another only from the left side
+///
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(trim_left("nata", vec!["a"])?, "nata");
+///     assert_eq!(trim_left("nata", vec!["n"])?, "ata");
+///     assert_eq!(trim_left("  \tnata\t  ", vec![])?, "nata\t  ");
+///     assert_eq!(trim_left(" !hi there! ", vec![" ", "!"])?, "hi there! ");
+/// # Ok(())
+/// # }
+pub fn trim_left(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "ltrim";
+    check_arguments_len(func_name, .., args.len())?;
+    if args.is_empty() {
+        return Ok(val.trim_start().to_string());
+    }
+    let mut val = val;
+    for arg in args {
+        val = val.trim_start_matches(|c| arg.contains(c))
+    }
+
+    Ok(val.to_string())
+}
+
+/// Trim the given string with given patterns one after another only from the right side
+///
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(trim_right("nata", vec!["a"])?, "nat");
+///     assert_eq!(trim_right("nata", vec!["n"])?, "nata");
+///     assert_eq!(trim_right("  \tnata\t  ", vec![])?, "  \tnata");
+///     assert_eq!(trim_right(" !hi there! ", vec![" ", "!"])?, " !hi there");
+/// # Ok(())
+/// # }
+pub fn trim_right(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "rtrim";
+    check_arguments_len(func_name, .., args.len())?;
+    if args.is_empty() {
+        return Ok(val.trim_end().to_string());
+    }
+    let mut val = val;
+    for arg in args {
+        val = val.trim_end_matches(|c| arg.contains(c))
+    }
+
+    Ok(val.to_string())
+}
+
+/// Remove the prefix from the text once, the text is unchanged if it
+/// doesn't start with the prefix. Multiple arguments are joined with
+/// comma, as the prefix can have them.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(strip_prefix("/home/user/file", vec!["/home/"])?, "user/file");
+///     assert_eq!(strip_prefix("/tmp/file", vec!["/home/"])?, "/tmp/file");
+///     assert_eq!(strip_prefix("aaab", vec!["a"])?, "aab");
+///     assert_eq!(strip_prefix("a,b,c", vec!["a", "b"])?, ",c");
+/// # Ok(())
+/// # }
+pub fn strip_prefix(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "stripprefix";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let prefix = args.join(",");
+    Ok(val.strip_prefix(&prefix).unwrap_or(val).to_string())
+}
+
+/// Remove the suffix from the text once, the text is unchanged if it
+/// doesn't end with the suffix. Multiple arguments are joined with
+/// comma, as the suffix can have them.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(strip_suffix("notes.txt", vec![".txt"])?, "notes");
+///     assert_eq!(strip_suffix("notes.md", vec![".txt"])?, "notes.md");
+///     assert_eq!(strip_suffix("a.txt.txt", vec![".txt"])?, "a.txt");
+/// # Ok(())
+/// # }
+pub fn strip_suffix(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "stripsuffix";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let suffix = args.join(",");
+    Ok(val.strip_suffix(&suffix).unwrap_or(val).to_string())
+}
+
+/// Insert commas to the given string in provided positions
+///
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(comma("1234", vec!["3"])?, "1,234");
+///     assert_eq!(comma("1234567", vec!["3"])?, "1,234,567");
+///     assert_eq!(comma("1234567", vec!["3", "2"])?, "12,34,567");
+///     assert_eq!(comma("91234567", vec!["3", "2"])?, "9,12,34,567");
+///     assert!(comma("1234", vec!["0"]).is_err());
+/// # Ok(())
+/// # }
+pub fn comma(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "comma";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let mut args: Vec<usize> = args
+        .iter()
+        .map(|s| {
+            s.parse().map_err(|_| {
+                TransformerError::InvalidArgumentType(func_name, s.to_string(), "uint")
+            })
+        })
+        .rev()
+        .collect::<Result<Vec<usize>, TransformerError>>()?;
+    if args.contains(&0) {
+        return Err(TransformerError::InvalidArgumentType(
+            func_name,
+            "0".to_string(),
+            "non-zero uint",
+        ));
+    }
+    let last = args[0];
+    let mut i = args.pop().unwrap();
+
+    let mut result = vec![];
+    let val: Vec<char> = val.replace(',', "").chars().rev().collect();
+    for c in val {
+        if i == 0 {
+            i = args.pop().unwrap_or(last);
+            result.push(',');
+        }
+        result.push(c);
+        i -= 1;
+    }
+    result.reverse();
+    let result: String = result.into_iter().collect();
+    Ok(result)
+}
+
+/// Insert characters to the given string in provided positions
+///
+/// With only one argument, the integer part of the number is grouped
+/// in thousands with the argument as separator, or in the Indian
+/// numbering system (lakh, crore) with commas if the argument is `in`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(group("1234", vec![",", "3"])?, "1,234");
+///     assert_eq!(group("1234567", vec!["_", "3"])?, "1_234_567");
+///     assert_eq!(group("1234567", vec![", ", "3", "2"])?, "12, 34, 567");
+///     assert_eq!(group("91234567", vec!["_", "3", "2"])?, "9_12_34_567");
+///     assert_eq!(group("1234567.891", vec!["_"])?, "1_234_567.891");
+///     assert_eq!(group("1234567", vec!["in"])?, "12,34,567");
+///     assert_eq!(group("-123456789.5", vec!["in"])?, "-12,34,56,789.5");
+///     assert_eq!(group("999", vec!["in"])?, "999");
+///     assert!(group("1234", vec!["_", "0"]).is_err());
+/// # Ok(())
+/// # }
+pub fn group(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "group";
+    check_arguments_len(func_name, 1.., args.len())?;
+    if let [style] = args[..] {
+        let sizes = if style == "in" {
+            vec![",", "3", "2"]
+        } else {
+            vec![style, "3"]
+        };
+        let (sign, num) = match val.strip_prefix('-') {
+            Some(n) => ("-", n),
+            None => ("", val),
+        };
+        let (int, frac) = match num.split_once('.') {
+            Some((i, f)) => (i, format!(".{f}")),
+            None => (num, String::new()),
+        };
+        return Ok(format!("{sign}{}{frac}", group(int, sizes)?));
+    }
+    let sep = args[0];
+    let mut args: Vec<usize> = args[1..]
+        .iter()
+        .map(|s| {
+            s.parse().map_err(|_| {
+                TransformerError::InvalidArgumentType(func_name, s.to_string(), "uint")
+            })
+        })
+        .rev()
+        .collect::<Result<Vec<usize>, TransformerError>>()?;
+    if args.contains(&0) {
+        return Err(TransformerError::InvalidArgumentType(
+            func_name,
+            "0".to_string(),
+            "non-zero uint",
+        ));
+    }
+    let last = args[0];
+    let mut i = args.pop().unwrap();
+
+    let mut result = vec![];
+    let val: Vec<char> = val.replace(sep, "").chars().rev().collect();
+    for c in val {
+        if i == 0 {
+            i = args.pop().unwrap_or(last);
+            for c in sep.chars().rev() {
+                result.push(c);
+            }
+        }
+        result.push(c);
+        i -= 1;
+    }
+    result.reverse();
+    let result: String = result.into_iter().collect();
+    Ok(result)
+}
+
+/// Quote the text with given strings or `""`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(quote("nata", vec![])?, "\"nata\"");
+///     assert_eq!(quote("nata", vec!["'"])?, "'nata'");
+///     assert_eq!(quote("na\"ta", vec![])?, "\"na\\\"ta\"");
+///     assert_eq!(quote("na'ta", vec!["'"])?, "'na\\'ta'");
+///     assert_eq!(quote("nata", vec!["`", "'"])?, "`nata'");
+/// # Ok(())
+/// # }
+pub fn quote(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "quote";
+    check_arguments_len(func_name, ..=2, args.len())?;
+    Ok(if args.is_empty() {
+        format!("{:?}", val)
+    } else if args.len() == 1 {
+        if args[0].is_empty() {
+            format!("{:?}", val)
+        } else {
+            format!(
+                "{0}{1}{0}",
+                args[0],
+                val.replace(args[0], &format!("\\{}", args[0]))
+            )
+        }
     } else {
-        val.splitn(
-            args[2].parse().map_err(|_| {
-                TransformerError::InvalidArgumentType(func_name, args[1].to_string(), "int")
-            })?,
+        format!(
+            "{}{}{}",
             args[0],
+            val.replace(args[0], &format!("\\{}", args[0]))
+                .replace(args[1], &format!("\\{}", args[1])),
+            args[1]
         )
-        .nth(n - 1)
+    })
+}
+
+/// Remove one layer of matching quotes (`"`, `'` or `` ` ``) from the
+/// text, and unescape the quotes inside it; inverse of [`quote`]. If
+/// a quote string is given, only that is removed. The text is
+/// returned unchanged if it's not quoted.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(unquote("\"nata\"", vec![])?, "nata");
+///     assert_eq!(unquote("'nata'", vec![])?, "nata");
+///     assert_eq!(unquote("`nata`", vec![""])?, "nata");
+///     assert_eq!(unquote("nata", vec![])?, "nata");
+///     assert_eq!(unquote("\"nata'", vec![])?, "\"nata'");
+///     assert_eq!(unquote("'nata'", vec!["\""])?, "'nata'");
+///     assert_eq!(unquote(&quote("na\"ta", vec![])?, vec![])?, "na\"ta");
+/// # Ok(())
+/// # }
+pub fn unquote(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "unquote";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let quotes: Vec<&str> = match args.first() {
+        Some(q) if !q.is_empty() => vec![q],
+        _ => vec!["\"", "'", "`"],
+    };
+    for q in quotes {
+        if val.len() >= 2 * q.len() && val.starts_with(q) && val.ends_with(q) {
+            let inner = &val[q.len()..(val.len() - q.len())];
+            return Ok(inner.replace(&format!("\\{q}"), q));
+        }
+    }
+    Ok(val.to_string())
+}
+
+/// Interpret the backslash escape sequences (`\n`, `\t`, `\r`, `\0`,
+/// `\\`, `\"`, `\'` and `\uXXXX`) in the text into the characters they
+/// represent. Other escape sequences are errors.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(unescape("line1\\nline2", vec![])?, "line1\nline2");
+///     assert_eq!(unescape("a\\tb\\\\c", vec![])?, "a\tb\\c");
+///     assert_eq!(unescape("caf\\u00e9", vec![])?, "café");
+///     assert_eq!(unescape("a\\tb", vec![""])?, "a\tb");
+///     assert!(unescape("bad\\q", vec![]).is_err());
+///     assert!(unescape("bad\\u00", vec![]).is_err());
+///     assert!(unescape("trailing\\", vec![]).is_err());
+/// # Ok(())
+/// # }
+pub fn unescape(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "unescape";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let invalid = |seq: &str| {
+        TransformerError::InvalidSyntax(val.to_string(), format!("Invalid escape sequence {seq}"))
+    };
+    let mut result = String::with_capacity(val.len());
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let esc = chars.next().ok_or_else(|| invalid("\\"))?;
+        result.push(match esc {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' | '"' | '\'' => esc,
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 4)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(&format!("\\u{hex}")))?
+            }
+            _ => return Err(invalid(&format!("\\{esc}"))),
+        });
+    }
+    Ok(result)
+}
+
+/// Convert the path separators to unix (`/`) or windows (`\`) style,
+/// or normalize the `.` and `..` components in the path. Multiple
+/// arguments are applied one after another.
+///
+/// This is purely string based, the path is not checked in the filesystem.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(path_sep("dir\\sub\\file.txt", vec!["unix"])?, "dir/sub/file.txt");
+///     assert_eq!(path_sep("dir/sub/file.txt", vec!["win"])?, "dir\\sub\\file.txt");
+///     assert_eq!(path_sep("/home/./user/../file.txt", vec!["norm"])?, "/home/file.txt");
+///     assert_eq!(path_sep("../a/./b/../c/", vec!["norm"])?, "../a/c");
+///     assert_eq!(path_sep("a\\..\\b\\.\\c", vec!["norm", "unix"])?, "b/c");
+/// # Ok(())
+/// # }
+pub fn path_sep(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "path";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let mut val = val.to_string();
+    for arg in args {
+        val = match arg {
+            "unix" => val.replace('\\', "/"),
+            "win" => val.replace('/', "\\"),
+            "norm" => normalize_path(&val),
+            a => {
+                return Err(TransformerError::InvalidArgumentType(
+                    func_name,
+                    a.to_string(),
+                    "{unix;win;norm}",
+                ))
+            }
+        };
+    }
+    Ok(val)
+}
+
+/// Extension of the file in the path, empty if there is none. The
+/// path is not checked in the filesystem.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(extension("/home/user/notes.tar.gz", vec![])?, "gz");
+///     assert_eq!(extension("/home/user/notes", vec![])?, "");
+///     assert_eq!(extension("/home/user/.bashrc", vec![])?, "");
+///     assert_eq!(extension("/home/user/", vec![])?, "");
+/// # Ok(())
+/// # }
+pub fn extension(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "ext";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    Ok(std::path::Path::new(val)
+        .extension()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default())
+}
+
+/// Last component of the path, trailing slash is ignored. The path
+/// is not checked in the filesystem.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(basename("/home/user/notes.txt", vec![])?, "notes.txt");
+///     assert_eq!(basename("/home/user/notes", vec![])?, "notes");
+///     assert_eq!(basename("/home/user/", vec![])?, "user");
+///     assert_eq!(basename("/", vec![])?, "");
+/// # Ok(())
+/// # }
+pub fn basename(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "basename";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    Ok(std::path::Path::new(val)
+        .file_name()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default())
+}
+
+/// Parent directory of the path, trailing slash is ignored. Empty if
+/// the path has no parent. The path is not checked in the filesystem.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(dirname("/home/user/notes.txt", vec![])?, "/home/user");
+///     assert_eq!(dirname("/home/user/notes", vec![])?, "/home/user");
+///     assert_eq!(dirname("/home/user/", vec![])?, "/home");
+///     assert_eq!(dirname("notes.txt", vec![])?, "");
+/// # Ok(())
+/// # }
+pub fn dirname(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "dirname";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    Ok(std::path::Path::new(val)
+        .parent()
+        .map(|e| e.to_string_lossy().to_string())
+        .unwrap_or_default())
+}
+
+/// Remove the `.` and `..` components from the path string, uses `\`
+/// as separator if the path has it and doesn't have `/`.
+fn normalize_path(val: &str) -> String {
+    let sep = if val.contains('\\') && !val.contains('/') {
+        '\\'
+    } else {
+        '/'
+    };
+    let absolute = val.starts_with(sep);
+    let mut comps: Vec<&str> = Vec::new();
+    for comp in val.split(sep) {
+        match comp {
+            "" | "." => (),
+            ".." => {
+                if comps.last().map(|c| *c != "..").unwrap_or(false) {
+                    comps.pop();
+                } else if !absolute {
+                    comps.push(comp);
+                }
+            }
+            c => comps.push(c),
+        }
+    }
+    let path = comps.join(&sep.to_string());
+    if absolute {
+        format!("{sep}{path}")
+    } else if path.is_empty() {
+        ".".to_string()
+    } else {
+        path
+    }
+}
+
+/// Format a list separated by the given separator (default `,`) as
+/// English prose with Oxford comma, the second argument is the
+/// conjunction to use (default `and`).
+///
+/// As the arguments are separated by comma, `{val:oxford(,)}` and
+/// `{val:oxford()}` both use comma as separator, and
+/// `{val:oxford(,,or)}` uses comma with `or`.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(oxford("", vec!["", ""])?, "");
+///     assert_eq!(oxford("a", vec!["", ""])?, "a");
+///     assert_eq!(oxford("a,b", vec!["", ""])?, "a and b");
+///     assert_eq!(oxford("a, b,c", vec!["", ""])?, "a, b, and c");
+///     assert_eq!(oxford("a,b,c", vec!["", "", "or"])?, "a, b, or c");
+///     assert_eq!(oxford("a;b;c", vec![";"])?, "a, b, and c");
+///     assert_eq!(oxford("a;b", vec![";", "or"])?, "a or b");
+/// # Ok(())
+/// # }
+pub fn oxford(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "oxford";
+    check_arguments_len(func_name, ..=3, args.len())?;
+    let raw = args.join(",");
+    let (sep, conj) = if let Some(rest) = raw.strip_prefix(',') {
+        (",", rest.strip_prefix(',').unwrap_or(rest))
+    } else {
+        raw.split_once(',').unwrap_or((&raw, ""))
+    };
+    let sep = if sep.is_empty() { "," } else { sep };
+    let conj = if conj.is_empty() { "and" } else { conj };
+    let items: Vec<&str> = val
+        .split(sep)
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    Ok(match items.as_slice() {
+        [] => String::new(),
+        [a] => a.to_string(),
+        [a, b] => format!("{a} {conj} {b}"),
+        [rest @ .., last] => format!("{}, {conj} {last}", rest.join(", ")),
+    })
+}
+
+/// Format a list separated by the given separator (default `,`) as
+/// numbered lines. The second argument is the format of the number,
+/// where `%d` is replaced by the index (default `"%d. "`), it can be
+/// quoted to keep the spaces. The third argument is the starting
+/// index (default 1).
+///
+/// Like [`oxford`], `{val:enumerate(,)}` and `{val:enumerate()}` both
+/// use comma as separator, and `{val:enumerate(,,"%d) ")}` uses comma
+/// with the given format.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(enumerate("a,b,c", vec!["", ""])?, "1. a\n2. b\n3. c");
+///     assert_eq!(enumerate("a, b", vec!["", "", "\"%d) \""])?, "1) a\n2) b");
+///     assert_eq!(enumerate("a;b", vec![";", "- [%d] ", "0"])?, "- [0] a\n- [1] b");
+///     assert_eq!(enumerate("", vec![""])?, "");
+///     assert!(enumerate("a", vec![";", "", "x"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn enumerate(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "enumerate";
+    check_arguments_len(func_name, ..=4, args.len())?;
+    let raw = args.join(",");
+    let (sep, rest) = if let Some(rest) = raw.strip_prefix(',') {
+        (",", rest.strip_prefix(',').unwrap_or(rest))
+    } else {
+        raw.split_once(',').unwrap_or((&raw, ""))
+    };
+    let (fmt, start) = rest.split_once(',').unwrap_or((rest, ""));
+    let sep = if sep.is_empty() { "," } else { sep };
+    let fmt = match fmt {
+        "" => "%d. ",
+        f if f.len() > 1 && f.starts_with('"') && f.ends_with('"') => &f[1..(f.len() - 1)],
+        f => f,
+    };
+    let start: usize = if start.is_empty() {
+        1
+    } else {
+        start.parse().map_err(|_| {
+            TransformerError::InvalidArgumentType(func_name, start.to_string(), "uint")
+        })?
+    };
+    Ok(val
+        .split(sep)
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .enumerate()
+        .map(|(i, item)| format!("{}{item}", fmt.replace("%d", &(start + i).to_string())))
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+/// Get the value for the given key from the text with key=value
+/// pairs, optional second and third arguments are the separators
+/// between pairs (default `;`) and between key and value (default
+/// `=`). Gives empty string if the key is not present.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(field("name=bob;age=30", vec!["name"])?, "bob");
+///     assert_eq!(field("name=bob; age=30", vec!["age"])?, "30");
+///     assert_eq!(field("name=bob;age=30", vec!["job"])?, "");
+///     assert_eq!(field("name:bob|age:30", vec!["age", "|", ":"])?, "30");
+///     assert_eq!(field("name:bob|age:30", vec!["job", "|", ":"])?, "");
+/// # Ok(())
+/// # }
+pub fn field(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "field";
+    check_arguments_len(func_name, 1..=3, args.len())?;
+    let key = args[0];
+    let pair_sep = args.get(1).filter(|s| !s.is_empty()).unwrap_or(&";");
+    let kv_sep = args.get(2).filter(|s| !s.is_empty()).unwrap_or(&"=");
+    Ok(val
+        .split(pair_sep)
+        .filter_map(|pair| pair.split_once(kv_sep))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, v)| v.trim().to_string())
+        .unwrap_or_default())
+}
+
+/// Evaluate the lisp expression with the value bound to the variable
+/// `self`, so it can be accessed with `(st+var 'self)` or `(st+num
+/// 'self)`. While rendering a template, other variables are also
+/// available (see [`apply_tranformers_with_options`]). String results
+/// are given without the quotes.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(lisp_transform("21", vec!["(* 2 (st+num 'self))"])?, "42");
+///     assert_eq!(lisp_transform("hi", vec!["(st+var 'self)"])?, "hi");
+///     assert!(lisp_transform("hi", vec!["(* 2 (st+num 'self))"]).is_err());
+/// # Ok(())
+/// # }
+pub fn lisp_transform(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    lisp_transform_with_vars(val, args, &HashMap::new())
+}
+
+/// Evaluate the lisp expression like [`lisp_transform`] with the
+/// given variables in addition to `self`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("n".into(), "3".into());
+///     let expr = "(* (st+num 'n) (st+num 'self))";
+///     assert_eq!(lisp_transform_with_vars("2", vec![expr], &vars)?, "6");
+/// # Ok(())
+/// # }
+pub fn lisp_transform_with_vars(
+    val: &str,
+    args: Vec<&str>,
+    variables: &HashMap<String, String>,
+) -> Result<String, TransformerError> {
+    lisp_transform_inner(val, args, variables, None)
+}
+
+/// Evaluate the lisp expression like [`lisp_transform_with_vars`],
+/// failing if it takes longer than the timeout, see
+/// [`lisp::evaluate_timeout`].
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use std::time::Duration;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let vars: HashMap<String, String> = HashMap::new();
+///     let timeout = Duration::from_secs(1);
+///     assert_eq!(lisp_transform_timeout("2", vec!["(* 3 (st+num 'self))"], &vars, timeout)?, "6");
+/// # Ok(())
+/// # }
+pub fn lisp_transform_timeout(
+    val: &str,
+    args: Vec<&str>,
+    variables: &HashMap<String, String>,
+    timeout: Duration,
+) -> Result<String, TransformerError> {
+    lisp_transform_inner(val, args, variables, Some(timeout))
+}
+
+fn lisp_transform_inner(
+    val: &str,
+    args: Vec<&str>,
+    variables: &HashMap<String, String>,
+    timeout: Option<Duration>,
+) -> Result<String, TransformerError> {
+    let func_name = "lisp";
+    check_arguments_len(func_name, 1.., args.len())?;
+    // arguments are split at commas, lisp might have them
+    let expr = args.join(",");
+    let mut vars = variables.clone();
+    vars.insert("self".to_string(), val.to_string());
+    let res = match timeout {
+        Some(t) => lisp::evaluate_timeout(&vars, &expr, t),
+        None => lisp::evaluate(&vars, &expr).map(|v| match v {
+            rust_lisp::model::Value::String(s) => s,
+            v => v.to_string(),
+        }),
+    };
+    res.map_err(|e| TransformerError::InvalidExpression(func_name, expr, e.to_string()))
+}
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const SCALES: [(u64, &str); 4] = [
+    (1_000_000_000_000, "trillion"),
+    (1_000_000_000, "billion"),
+    (1_000_000, "million"),
+    (1_000, "thousand"),
+];
+
+/// English words for numbers below a thousand
+fn words_below_thousand(num: u64) -> String {
+    let (hundreds, rest) = (num / 100, num % 100);
+    let mut parts = Vec::new();
+    if hundreds > 0 {
+        parts.push(format!("{} hundred", ONES[hundreds as usize]));
+    }
+    if rest >= 20 {
+        let (tens, ones) = (rest / 10, rest % 10);
+        if ones > 0 {
+            parts.push(format!("{}-{}", TENS[tens as usize], ONES[ones as usize]));
+        } else {
+            parts.push(TENS[tens as usize].to_string());
+        }
+    } else if rest > 0 || hundreds == 0 {
+        parts.push(ONES[rest as usize].to_string());
+    }
+    parts.join(" ")
+}
+
+/// Write the integer in English words, numbers up to trillions are
+/// supported. Using `keep` as the argument gives the number as it is
+/// instead of an error when it is out of range.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(words("0", vec![])?, "zero");
+///     assert_eq!(words("13", vec![])?, "thirteen");
+///     assert_eq!(words("123", vec![])?, "one hundred twenty-three");
+///     assert_eq!(words("-40", vec![])?, "minus forty");
+///     assert_eq!(words("1005000", vec![])?, "one million five thousand");
+///     assert_eq!(words("2000000000000", vec![])?, "two trillion");
+///     assert!(words("1000000000000000", vec![]).is_err());
+///     assert_eq!(words("1000000000000000", vec!["keep"])?, "1000000000000000");
+///     assert!(words("-99999999999999999999999999999999999999999", vec![]).is_err());
+///     assert!(words("1.5", vec![]).is_err());
+/// # Ok(())
+/// # }
+pub fn words(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "words";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let keep = match args.first() {
+        None | Some(&"") => false,
+        Some(&"keep") => true,
+        Some(a) => {
+            return Err(TransformerError::InvalidArgumentType(
+                func_name,
+                a.to_string(),
+                "keep",
+            ))
+        }
+    };
+    let num = match parse_integer(func_name, val.trim()) {
+        Err(TransformerError::ValueOutOfRange(..)) => i128::MAX,
+        n => n?,
+    };
+    if num.unsigned_abs() >= 1_000_000_000_000_000 {
+        return if keep {
+            Ok(val.to_string())
+        } else {
+            Err(TransformerError::ValueOutOfRange(
+                func_name,
+                val.to_string(),
+                "below a quadrillion",
+            ))
+        };
+    }
+    let mut abs = num.unsigned_abs() as u64;
+    let mut parts = Vec::new();
+    if num < 0 {
+        parts.push("minus".to_string());
+    }
+    for (scale, name) in SCALES {
+        if abs >= scale {
+            parts.push(format!("{} {name}", words_below_thousand(abs / scale)));
+            abs %= scale;
+        }
+    }
+    if abs > 0 || parts.iter().all(|p| p == "minus") {
+        parts.push(words_below_thousand(abs));
+    }
+    Ok(parts.join(" "))
+}
+
+/// Parse the ISO 8601 duration like `P1DT1H30M` into seconds, years
+/// and months are not supported as they don't have fixed length
+fn parse_iso_duration(val: &str) -> Option<f64> {
+    let (sign, val) = match val.strip_prefix('-') {
+        Some(v) => (-1.0, v),
+        None => (1.0, val),
+    };
+    let rest = val.strip_prefix('P')?;
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return None,
+        Some((d, t)) => (d, Some(t)),
+        None => (rest, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return None;
+    }
+    let components = |text: &str, units: &[(char, f64)]| -> Option<f64> {
+        let mut total = 0.0;
+        let mut num = String::new();
+        let mut units = units.iter();
+        for c in text.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                num.push(c);
+            } else {
+                // units need to be in order, and each only once
+                let (_, secs) = units.find(|(u, _)| *u == c)?;
+                total += num.parse::<f64>().ok()? * secs;
+                num.clear();
+            }
+        }
+        num.is_empty().then_some(total)
     };
+    let mut secs = components(date, &[('W', 604800.0), ('D', 86400.0)])?;
+    if let Some(time) = time {
+        secs += components(time, &[('H', 3600.0), ('M', 60.0), ('S', 1.0)])?;
+    }
+    Some(sign * secs)
+}
 
-    Ok(spl.unwrap_or("").to_string())
+/// Format the seconds into ISO 8601 duration like `PT1H30M`
+fn format_iso_duration(secs: f64) -> String {
+    let sign = if secs < 0.0 { "-" } else { "" };
+    let secs = secs.abs();
+    let whole = secs.trunc() as u64;
+    let (days, hours, mins) = (whole / 86400, whole % 86400 / 3600, whole % 3600 / 60);
+    let secs = secs - (whole - whole % 60) as f64;
+    let mut dur = format!("{sign}P");
+    if days > 0 {
+        dur.push_str(&format!("{days}D"));
+    }
+    if hours > 0 || mins > 0 || secs > 0.0 || days == 0 {
+        dur.push('T');
+        if hours > 0 {
+            dur.push_str(&format!("{hours}H"));
+        }
+        if mins > 0 {
+            dur.push_str(&format!("{mins}M"));
+        }
+        if secs > 0.0 || (hours == 0 && mins == 0 && days == 0) {
+            dur.push_str(&format!("{secs}S"));
+        }
+    }
+    dur
 }
 
-/// Trim the given string with given patterns one after another
+/// Convert between ISO 8601 durations (like `PT1H30M`) and seconds.
 ///
+/// The optional argument is the output format: `secs` for seconds,
+/// `hms` for `H:MM:SS`, or `iso` for ISO 8601 duration. Without it,
+/// durations are converted to seconds and numbers to durations.
+/// Durations with years or months are errors as their length in
+/// seconds is not fixed.
 ///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(trim("nata", vec!["a"])?, "nat");
-///     assert_eq!(trim("  \tnata\t  ", vec![])?, "nata");
-///     assert_eq!(trim("hi there! ", vec![" ", "!"])?, "hi there");
-///     assert_eq!(trim("hi there! ", vec![" !", "ih"])?, " there");
+///     assert_eq!(iso_duration("PT1H30M", vec![])?, "5400");
+///     assert_eq!(iso_duration("P1DT0.5S", vec![])?, "86400.5");
+///     assert_eq!(iso_duration("5400", vec![])?, "PT1H30M");
+///     assert_eq!(iso_duration("90061", vec![])?, "P1DT1H1M1S");
+///     assert_eq!(iso_duration("0", vec![])?, "PT0S");
+///     assert_eq!(iso_duration("PT1H30M5S", vec!["hms"])?, "1:30:05");
+///     assert_eq!(iso_duration("PT90M", vec!["iso"])?, "PT1H30M");
+///     assert!(iso_duration("P1Y", vec![]).is_err());
+///     assert!(iso_duration("PT1H30", vec![]).is_err());
+///     assert!(iso_duration("1H30M", vec![]).is_err());
+///     assert!(iso_duration("1e30", vec!["hms"]).is_err());
 /// # Ok(())
 /// # }
-pub fn trim(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "trim";
-    check_arguments_len(func_name, .., args.len())?;
-    if args.is_empty() {
-        return Ok(val.trim().to_string());
+pub fn iso_duration(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "isodur";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let val = val.trim();
+    let (secs, is_iso) = match val.parse::<f64>() {
+        Ok(s) if s.is_finite() => (s, false),
+        _ => (
+            parse_iso_duration(val).ok_or(TransformerError::InvalidValueType(
+                func_name,
+                "ISO 8601 duration or seconds",
+            ))?,
+            true,
+        ),
+    };
+    let format = match args.first() {
+        None | Some(&"") if is_iso => "secs",
+        None | Some(&"") => "iso",
+        Some(f) => f,
+    };
+    if format != "secs" && secs.abs() >= u64::MAX as f64 {
+        return Err(TransformerError::ValueOutOfRange(
+            func_name,
+            val.to_string(),
+            "below 2^64 seconds",
+        ));
     }
-    let mut val = val;
-    for arg in args {
-        val = val.trim_matches(|c| arg.contains(c))
+    match format {
+        "secs" => Ok(secs.to_string()),
+        "iso" => Ok(format_iso_duration(secs)),
+        "hms" => {
+            let sign = if secs < 0.0 { "-" } else { "" };
+            let whole = secs.abs().round() as u64;
+            Ok(format!(
+                "{sign}{}:{:02}:{:02}",
+                whole / 3600,
+                whole % 3600 / 60,
+                whole % 60
+            ))
+        }
+        f => Err(TransformerError::InvalidArgumentType(
+            func_name,
+            f.to_string(),
+            "secs/hms/iso",
+        )),
     }
-
-    Ok(val.to_string())
 }
 
-/// Insert commas to the given string in provided positions
-///
+/// Choose the singular (first argument) or plural (second argument)
+/// form based on the count in the value, zero is plural. With
+/// `withnum` as the third argument the count is included.
 ///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(comma("1234", vec!["3"])?, "1,234");
-///     assert_eq!(comma("1234567", vec!["3"])?, "1,234,567");
-///     assert_eq!(comma("1234567", vec!["3", "2"])?, "12,34,567");
-///     assert_eq!(comma("91234567", vec!["3", "2"])?, "9,12,34,567");
+///     assert_eq!(plural("0", vec!["item", "items"])?, "items");
+///     assert_eq!(plural("1", vec!["item", "items"])?, "item");
+///     assert_eq!(plural("3", vec!["item", "items"])?, "items");
+///     assert_eq!(plural("3", vec!["item", "items", "withnum"])?, "3 items");
+///     assert_eq!(plural("1", vec!["child", "children", "withnum"])?, "1 child");
+///     assert!(plural("many", vec!["item", "items"]).is_err());
 /// # Ok(())
 /// # }
-pub fn comma(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "comma";
-    check_arguments_len(func_name, 1.., args.len())?;
-    let mut args: Vec<usize> = args
-        .iter()
-        .map(|s| {
-            s.parse().map_err(|_| {
-                TransformerError::InvalidArgumentType(func_name, s.to_string(), "uint")
-            })
-        })
-        .rev()
-        .collect::<Result<Vec<usize>, TransformerError>>()?;
-    let last = args[0];
-    let mut i = args.pop().unwrap();
+pub fn plural(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "plural";
+    check_arguments_len(func_name, 2..=3, args.len())?;
+    let count: f64 = val
+        .trim()
+        .parse()
+        .map_err(|_| TransformerError::InvalidValueType(func_name, "number"))?;
+    let form = if count.abs() == 1.0 { args[0] } else { args[1] };
+    match args.get(2) {
+        None | Some(&"") => Ok(form.to_string()),
+        Some(&"withnum") => Ok(format!("{} {form}", val.trim())),
+        Some(a) => Err(TransformerError::InvalidArgumentType(
+            func_name,
+            a.to_string(),
+            "withnum",
+        )),
+    }
+}
 
-    let mut result = vec![];
-    let val: Vec<char> = val.replace(',', "").chars().rev().collect();
-    for c in val {
-        if i == 0 {
-            i = args.pop().unwrap_or(last);
-            result.push(',');
+/// Parse the date offset like `+3d`, `-1mo` into the number and unit
+fn parse_date_offset(offset: &str) -> Option<(i64, &str)> {
+    let offset = offset.trim();
+    let split = offset
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || (*i == 0 && (*c == '+' || *c == '-'))))
+        .map(|(i, _)| i)?;
+    let (num, unit) = offset.split_at(split);
+    Some((num.parse().ok()?, unit))
+}
+
+/// Shift the date by the given offset. The first argument is the
+/// chrono format to parse the value, the second is the offset with
+/// a number and unit (`y`, `mo`, `d`, `h` or `m`), and the optional
+/// third is the format for the output (default same as input).
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(date_offset("2024-02-27", vec!["%F", "+3d", "%F"])?, "2024-03-01");
+///     assert_eq!(date_offset("2024-01-01", vec!["%F", "-1d"])?, "2023-12-31");
+///     assert_eq!(date_offset("2024-01-31", vec!["%F", "1mo"])?, "2024-02-29");
+///     assert_eq!(date_offset("2024-02-29", vec!["%F", "+1y"])?, "2025-02-28");
+///     assert_eq!(date_offset("2024-01-01", vec!["%F", "+90m", "%F %H:%M"])?, "2024-01-01 01:30");
+///     assert_eq!(date_offset("2024-01-01 23.00", vec!["%F %H.%M", "+2h", "%F"])?, "2024-01-02");
+///     assert!(date_offset("01/01/2024", vec!["%F", "+1d"]).is_err());
+///     assert!(date_offset("2024-01-01", vec!["%F", "+1w"]).is_err());
+/// # Ok(())
+/// # }
+pub fn date_offset(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    use chrono::{Duration, Months, NaiveDate, NaiveDateTime};
+    let func_name = "dateoffset";
+    check_arguments_len(func_name, 2..=3, args.len())?;
+    let in_fmt = args[0];
+    let out_fmt = args.get(2).filter(|f| !f.is_empty()).unwrap_or(&in_fmt);
+    let date = NaiveDateTime::parse_from_str(val, in_fmt)
+        .or_else(|_| NaiveDate::parse_from_str(val, in_fmt).map(|d| d.and_time(Default::default())))
+        .map_err(|e| {
+            TransformerError::InvalidExpression(func_name, val.to_string(), e.to_string())
+        })?;
+    let invalid_offset =
+        || TransformerError::InvalidArgumentType(func_name, args[1].to_string(), "date offset");
+    let (num, unit) = parse_date_offset(args[1]).ok_or_else(invalid_offset)?;
+    let months = |m: i64| {
+        let m = Months::new(u32::try_from(m.unsigned_abs()).ok()?);
+        if num < 0 {
+            date.checked_sub_months(m)
+        } else {
+            date.checked_add_months(m)
         }
-        result.push(c);
-        i -= 1;
+    };
+    let shifted = match unit {
+        "y" => num.checked_mul(12).and_then(months),
+        "mo" => months(num),
+        "d" => Duration::try_days(num).and_then(|d| date.checked_add_signed(d)),
+        "h" => Duration::try_hours(num).and_then(|d| date.checked_add_signed(d)),
+        "m" => Duration::try_minutes(num).and_then(|d| date.checked_add_signed(d)),
+        _ => return Err(invalid_offset()),
     }
-    result.reverse();
-    let result: String = result.into_iter().collect();
-    Ok(result)
+    .ok_or_else(|| TransformerError::ValueOutOfRange(func_name, val.to_string(), "a valid date"))?;
+    Ok(shifted.format(out_fmt).to_string())
 }
 
-/// Insert characters to the given string in provided positions
+/// How long ago the date was from now in words, like `3 days ago`,
+/// `in 2 hours` or `just now`. The argument is the chrono format to
+/// parse the value. While rendering, [`RenderOptions::now`] is used
+/// as the current time if it's set, see [`ago_since`].
+/// e.g. `{created:ago(%F)}`
 ///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert!(ago("2000-01-01", vec!["%F"])?.ends_with("years ago"));
+///     assert!(ago("01/01/2000", vec!["%F"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn ago(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    ago_since(val, args, chrono::Local::now().naive_local())
+}
+
+/// Same as [`ago`] but with the given time as the current time.
 ///
 /// ```rust
 /// # use std::error::Error;
+/// # use chrono::NaiveDate;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(group("1234", vec![",", "3"])?, "1,234");
-///     assert_eq!(group("1234567", vec!["_", "3"])?, "1_234_567");
-///     assert_eq!(group("1234567", vec![", ", "3", "2"])?, "12, 34, 567");
-///     assert_eq!(group("91234567", vec!["_", "3", "2"])?, "9_12_34_567");
+///     let now = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap().and_hms_opt(12, 0, 0).unwrap();
+///     assert_eq!(ago_since("2024-03-07", vec!["%F"], now)?, "3 days ago");
+///     assert_eq!(ago_since("2024-03-10 11:59:30", vec!["%F %T"], now)?, "just now");
+///     assert_eq!(ago_since("2024-03-10 11:00", vec!["%F %H:%M"], now)?, "1 hour ago");
+///     assert_eq!(ago_since("2024-03-10 11:15", vec!["%F %H:%M"], now)?, "45 minutes ago");
+///     assert_eq!(ago_since("2024-01-01", vec!["%F"], now)?, "2 months ago");
+///     assert_eq!(ago_since("2021-03-10", vec!["%F"], now)?, "3 years ago");
+///     assert_eq!(ago_since("2024-03-12 12:00", vec!["%F %H:%M"], now)?, "in 2 days");
+///     assert!(ago_since("yesterday", vec!["%F"], now).is_err());
 /// # Ok(())
 /// # }
-pub fn group(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "group";
-    check_arguments_len(func_name, 2.., args.len())?;
-    let sep = args[0];
-    let mut args: Vec<usize> = args[1..]
-        .iter()
-        .map(|s| {
-            s.parse().map_err(|_| {
-                TransformerError::InvalidArgumentType(func_name, s.to_string(), "uint")
-            })
+/// ```
+pub fn ago_since(
+    val: &str,
+    args: Vec<&str>,
+    now: chrono::NaiveDateTime,
+) -> Result<String, TransformerError> {
+    use chrono::{NaiveDate, NaiveDateTime};
+    let func_name = "ago";
+    check_arguments_len(func_name, 1..=1, args.len())?;
+    let date = NaiveDateTime::parse_from_str(val, args[0])
+        .or_else(|_| {
+            NaiveDate::parse_from_str(val, args[0]).map(|d| d.and_time(Default::default()))
         })
-        .rev()
-        .collect::<Result<Vec<usize>, TransformerError>>()?;
-    let last = args[0];
-    let mut i = args.pop().unwrap();
+        .map_err(|e| {
+            TransformerError::InvalidExpression(func_name, val.to_string(), e.to_string())
+        })?;
+    let secs = (now - date).num_seconds();
+    let (num, unit) = match secs.unsigned_abs() {
+        s if s < 60 => return Ok("just now".to_string()),
+        s if s < 3600 => (s / 60, "minute"),
+        s if s < 86400 => (s / 3600, "hour"),
+        s if s < 30 * 86400 => (s / 86400, "day"),
+        s if s < 365 * 86400 => (s / (30 * 86400), "month"),
+        s => (s / (365 * 86400), "year"),
+    };
+    let plural = if num == 1 { "" } else { "s" };
+    Ok(if secs > 0 {
+        format!("{num} {unit}{plural} ago")
+    } else {
+        format!("in {num} {unit}{plural}")
+    })
+}
 
-    let mut result = vec![];
-    let val: Vec<char> = val.replace(sep, "").chars().rev().collect();
-    for c in val {
-        if i == 0 {
-            i = args.pop().unwrap_or(last);
-            for c in sep.chars().rev() {
-                result.push(c);
-            }
+/// Extract the capture groups of the regex (first argument) into the
+/// format (second argument), where `$N` or `${N}` refers to the Nth
+/// group and `$name` to the named groups. The value is given as it is
+/// if the regex doesn't match. e.g. `{d:reformat((\d+)-(\d+),$2/$1)}`
+///
+/// Like [`when`], the regex can't have `?` or `}` in the template,
+/// and the commas after the first one are part of the format.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(reformat("3-2024", vec![r"(\d+)-(\d+)", "$2/$1"])?, "2024/3");
+///     assert_eq!(reformat("v3-2024", vec![r"(\d+)-(\d+)", "$2/$1"])?, "2024/3");
+///     assert_eq!(reformat("march", vec![r"(\d+)-(\d+)", "$2/$1"])?, "march");
+///     assert_eq!(reformat("a=1", vec![r"(\w)=(\d)", "${2}", "$1"])?, "1,a");
+///     assert!(reformat("a", vec![r"(\d", "$1"]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn reformat(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "reformat";
+    check_arguments_len(func_name, 2.., args.len())?;
+    let re = Regex::new(args[0]).map_err(|e| {
+        TransformerError::InvalidExpression(func_name, args[0].to_string(), e.to_string())
+    })?;
+    Ok(match re.captures(val) {
+        Some(caps) => {
+            let mut result = String::new();
+            caps.expand(&args[1..].join(","), &mut result);
+            result
         }
-        result.push(c);
-        i -= 1;
-    }
-    result.reverse();
-    let result: String = result.into_iter().collect();
-    Ok(result)
+        None => val.to_string(),
+    })
 }
 
-/// Quote the text with given strings or `""`
+/// Keep only the characters of the given class, which can be one of
+/// `alnum`, `alpha`, `digit`, `space`, `upper`, `lower`, `punct` or a
+/// regex pattern starting with `re:`, see [`strip`] to remove them
+/// instead. e.g. `{s:keep(alnum)}` or `{s:keep(re:[a-z])}`
 ///
 /// ```rust
 /// # use std::error::Error;
 /// # use string_template_plus::transformers::*;
 /// #
 /// # fn main() -> Result<(), Box<dyn Error>> {
-///     assert_eq!(quote("nata", vec![])?, "\"nata\"");
-///     assert_eq!(quote("nata", vec!["'"])?, "'nata'");
-///     assert_eq!(quote("na\"ta", vec![])?, "\"na\\\"ta\"");
-///     assert_eq!(quote("na'ta", vec!["'"])?, "'na\\'ta'");
-///     assert_eq!(quote("nata", vec!["`", "'"])?, "`nata'");
+///     assert_eq!(keep("my-file_1.txt", vec!["alnum"])?, "myfile1txt");
+///     assert_eq!(keep("+1 (555) 010-9999", vec!["digit"])?, "15550109999");
+///     assert_eq!(keep("Hello World", vec!["re:[a-z]"])?, "elloorld");
+///     assert_eq!(keep("a1,b2", vec!["re:[a", "b]"])?, "a,b");
+///     assert!(keep("abc", vec!["vowels"]).is_err());
 /// # Ok(())
 /// # }
-pub fn quote(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
-    let func_name = "quote";
-    check_arguments_len(func_name, ..=2, args.len())?;
-    Ok(if args.is_empty() {
-        format!("{:?}", val)
-    } else if args.len() == 1 {
-        if args[0].is_empty() {
-            format!("{:?}", val)
+/// ```
+pub fn keep(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    filter_chars("keep", val, args, true)
+}
+
+/// Remove the characters of the given class, the classes are the
+/// same as in [`keep`]. e.g. `{s:strip(punct)}`
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(strip("my-file_1.txt", vec!["alnum"])?, "-_.");
+///     assert_eq!(strip("v1.2.3", vec!["digit"])?, "v..");
+///     assert_eq!(strip("Hello, World!", vec!["punct"])?, "Hello World");
+///     assert_eq!(strip("a  b\tc", vec!["space"])?, "abc");
+///     assert_eq!(strip("Hello World", vec!["re:[A-Z ]"])?, "elloorld");
+/// # Ok(())
+/// # }
+/// ```
+pub fn strip(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    filter_chars("strip", val, args, false)
+}
+
+/// Keep or remove the characters of the class in the arguments,
+/// the commas in the regex pattern are split into arguments
+fn filter_chars(
+    func_name: &'static str,
+    val: &str,
+    args: Vec<&str>,
+    keep: bool,
+) -> Result<String, TransformerError> {
+    check_arguments_len(func_name, 1.., args.len())?;
+    let class = args.join(",");
+    if let Some(pat) = class.strip_prefix("re:") {
+        let re = Regex::new(pat).map_err(|e| {
+            TransformerError::InvalidExpression(func_name, pat.to_string(), e.to_string())
+        })?;
+        return Ok(if keep {
+            re.find_iter(val).map(|m| m.as_str()).collect()
         } else {
-            format!(
-                "{0}{1}{0}",
-                args[0],
-                val.replace(args[0], &format!("\\{}", args[0]))
-            )
+            re.replace_all(val, "").into_owned()
+        });
+    }
+    let matches: fn(&char) -> bool = match class.as_str() {
+        "alnum" => |c| c.is_alphanumeric(),
+        "alpha" => |c| c.is_alphabetic(),
+        "digit" => |c| c.is_ascii_digit(),
+        "space" => |c| c.is_whitespace(),
+        "upper" => |c| c.is_uppercase(),
+        "lower" => |c| c.is_lowercase(),
+        "punct" => |c| c.is_ascii_punctuation(),
+        _ => {
+            return Err(TransformerError::InvalidArgumentType(
+                func_name,
+                class,
+                "{alnum,alpha,digit,space,upper,lower,punct,re:pattern}",
+            ))
         }
+    };
+    Ok(val.chars().filter(|c| matches(c) == keep).collect())
+}
+
+/// Give the second argument if the value matches the first argument,
+/// else the third argument (default the value itself). The first
+/// argument starting with `re:` is a regex pattern, otherwise the
+/// value needs to be exactly the same.
+///
+/// The regex pattern can't have `?` or `}` in the template as they
+/// have special meaning for the variable placeholders.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(when("503", vec!["re:^5", "error", "ok"])?, "error");
+///     assert_eq!(when("200", vec!["re:^5", "error", "ok"])?, "ok");
+///     assert_eq!(when("200", vec!["re:^5", "error"])?, "200");
+///     assert_eq!(when("yes", vec!["yes", "on", "off"])?, "on");
+///     assert_eq!(when("yess", vec!["yes", "on", "off"])?, "off");
+///     assert!(when("200", vec!["re:^(5", "error"]).is_err());
+/// # Ok(())
+/// # }
+pub fn when(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "when";
+    check_arguments_len(func_name, 2..=3, args.len())?;
+    let matches = match args[0].strip_prefix("re:") {
+        Some(pat) => Regex::new(pat)
+            .map_err(|e| {
+                TransformerError::InvalidExpression(func_name, pat.to_string(), e.to_string())
+            })?
+            .is_match(val),
+        None => val == args[0],
+    };
+    Ok(if matches {
+        args[1].to_string()
     } else {
-        format!(
-            "{}{}{}",
-            args[0],
-            val.replace(args[0], &format!("\\{}", args[0]))
-                .replace(args[1], &format!("\\{}", args[1])),
-            args[1]
-        )
+        args.get(2).unwrap_or(&val).to_string()
     })
 }
+
+/// Whether the text counts as true for the conditions. Empty text,
+/// `0`, `false`, `no`, `off`, `n` and `f` (case insensitive) are false,
+/// everything else is true.
+///
+/// ```rust
+/// # use string_template_plus::transformers::*;
+///     assert!(is_truthy("yes"));
+///     assert!(is_truthy("1"));
+///     assert!(!is_truthy(" False "));
+///     assert!(!is_truthy(""));
+/// ```
+pub fn is_truthy(val: &str) -> bool {
+    !matches!(
+        val.trim().to_lowercase().as_str(),
+        "" | "0" | "false" | "no" | "off" | "n" | "f"
+    )
+}
+
+/// Show the value only if the variable given in the argument is
+/// truthy (see [`is_truthy`]), else give empty string. Without the
+/// variables (see [`show_if_with_vars`]) the condition is always
+/// false.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(show_if("label", vec!["verbose"])?, "");
+/// # Ok(())
+/// # }
+pub fn show_if(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    show_if_with_vars(val, args, &HashMap::new())
+}
+
+/// Show the value only if the variable given in the argument is
+/// truthy (see [`is_truthy`]) in the variables, missing variables are
+/// false.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use std::collections::HashMap;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let mut vars: HashMap<String, String> = HashMap::new();
+///     vars.insert("verbose".into(), "true".into());
+///     vars.insert("quiet".into(), "0".into());
+///     assert_eq!(show_if_with_vars("label", vec!["verbose"], &vars)?, "label");
+///     assert_eq!(show_if_with_vars("label", vec!["quiet"], &vars)?, "");
+///     assert_eq!(show_if_with_vars("label", vec!["debug"], &vars)?, "");
+/// # Ok(())
+/// # }
+pub fn show_if_with_vars<V: Variables + ?Sized>(
+    val: &str,
+    args: Vec<&str>,
+    variables: &V,
+) -> Result<String, TransformerError> {
+    let func_name = "showif";
+    check_arguments_len(func_name, 1..=1, args.len())?;
+    if variables.get(args[0]).is_some_and(|c| is_truthy(&c)) {
+        Ok(val.to_string())
+    } else {
+        Ok(String::new())
+    }
+}
+
+/// Render the value as a template, without any variables (see
+/// [`render_template_with_options`]).
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(render_template("\\{hi\\} {name?\"you\"}", vec![""])?, "{hi} you");
+///     assert!(render_template("{name}", vec![""]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn render_template(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    render_template_with_options(val, args, &RenderOptions::default())
+}
+
+/// Render the value as a template with the [`RenderOptions`] used to
+/// render the template it's in, so it can use the same variables
+/// (and commands if they are enabled). Each nested rendering reduces
+/// the [`RenderOptions::max_depth`] by one, so values rendering
+/// themselves fail instead of looping forever.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::RenderOptions;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let mut options = RenderOptions::default();
+///     options.variables.insert("name".into(), "world".into());
+///     assert_eq!(render_template_with_options("hello {name}", vec![""], &options)?, "hello world");
+/// # Ok(())
+/// # }
+/// ```
+pub fn render_template_with_options(
+    val: &str,
+    args: Vec<&str>,
+    op: &RenderOptions,
+) -> Result<String, TransformerError> {
+    render_template_with_source(val, args, op, &op.variables)
+}
+
+/// Same as [`render_template_with_options`] with the values of the
+/// variables from `vars`, see [`crate::Template::render_with`]
+fn render_template_with_source(
+    val: &str,
+    args: Vec<&str>,
+    op: &RenderOptions,
+    vars: &dyn Variables,
+) -> Result<String, TransformerError> {
+    let func_name = "render";
+    check_arguments_len(func_name, ..=1, args.len())?;
+    let render = || -> Result<String, anyhow::Error> {
+        let depth = op.max_depth.unwrap_or(crate::MAX_RENDER_DEPTH);
+        if depth == 0 {
+            return Err(crate::errors::RenderTemplateError::MaxDepthExceeded(0).into());
+        }
+        let nested = RenderOptions {
+            max_depth: Some(depth - 1),
+            ..op.clone()
+        };
+        crate::Template::parse_template(val)?.render_with(vars, &nested)
+    };
+    render()
+        .map_err(|e| TransformerError::InvalidExpression(func_name, val.to_string(), e.to_string()))
+}
+
+/// Edit distance between the value and the argument, the number of
+/// characters to insert, delete or substitute to change one into the
+/// other. Multiple arguments are joined with comma.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # use string_template_plus::transformers::*;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     assert_eq!(levenshtein("kitten", vec!["kitten"])?, "0");
+///     assert_eq!(levenshtein("kitten", vec!["mitten"])?, "1");
+///     assert_eq!(levenshtein("kitten", vec!["sitting"])?, "3");
+///     assert_eq!(levenshtein("", vec!["abc"])?, "3");
+///     assert_eq!(levenshtein("a,b", vec!["a", "c"])?, "1");
+/// # Ok(())
+/// # }
+pub fn levenshtein(val: &str, args: Vec<&str>) -> Result<String, TransformerError> {
+    let func_name = "levenshtein";
+    check_arguments_len(func_name, 1.., args.len())?;
+    let other: Vec<char> = args.join(",").chars().collect();
+    let mut prev: Vec<usize> = (0..=other.len()).collect();
+    for (i, a) in val.chars().enumerate() {
+        let mut row = vec![i + 1; other.len() + 1];
+        for (j, b) in other.iter().enumerate() {
+            let cost = usize::from(a != *b);
+            row[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    Ok(prev[other.len()].to_string())
+}